@@ -0,0 +1,310 @@
+//! Attributes placed on the lines above a procedure declaration, such as `@locals(2)` or
+//! `@callconv("felt")`.
+
+use crate::{
+    config::{LocalsStyle, ProcedureConfig},
+    diagnostics::Diagnostic,
+    directives::protected_lines,
+    is_comment, is_decorator, is_proc_or_export,
+    signatures::{matching_paren, split_top_level},
+    split_inline_comment,
+};
+
+/// A single attribute: `@name` or `@name(arguments)`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Attribute {
+    pub name: String,
+    /// Arguments given in parentheses, or `None` if the attribute has no parentheses.
+    pub args: Option<Vec<String>>,
+    /// Inline comment following the attribute, including the `#`.
+    pub comment: Option<String>,
+}
+
+impl Attribute {
+    pub fn parse(line: &str) -> Option<Self> {
+        let (code, comment) = split_inline_comment(line.trim());
+        let body = code.trim().strip_prefix('@')?;
+        let name_end = body
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(body.len());
+        let name = &body[..name_end];
+        if name.is_empty() {
+            return None;
+        }
+
+        let rest = body[name_end..].trim_start();
+        let args = if rest.is_empty() {
+            None
+        } else {
+            if !rest.starts_with('(') || matching_paren(rest, 0)? != rest.len() - 1 {
+                return None;
+            }
+            let args = split_top_level(&rest[1..rest.len() - 1])
+                .into_iter()
+                .map(str::trim)
+                .filter(|arg| !arg.is_empty())
+                .map(str::to_string)
+                .collect();
+            Some(args)
+        };
+
+        Some(Self {
+            name: name.to_string(),
+            args,
+            comment: (!comment.is_empty()).then(|| comment.trim().to_string()),
+        })
+    }
+
+    /// Renders the attribute with no spaces inside its parentheses and one space after each
+    /// comma.
+    pub fn render(&self) -> String {
+        let mut line = format!("@{}", self.name);
+        if let Some(args) = &self.args {
+            line.push_str(&format!("({})", args.join(", ")));
+        }
+        if let Some(comment) = &self.comment {
+            line.push(' ');
+            line.push_str(comment);
+        }
+        line
+    }
+}
+
+/// Formats every run of attributes: canonical spacing, the configured order, and no empty lines
+/// between the attributes and the procedure they decorate. A run holding an attribute that cannot
+/// be parsed is left as written.
+pub(crate) fn format_attributes(lines: Vec<String>, config: &ProcedureConfig) -> Vec<String> {
+    let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let protected = protected_lines(&line_refs);
+
+    // Drop empty lines following an attribute, when the next item is an attribute or procedure.
+    let mut kept: Vec<(String, bool)> = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        let follows_attribute = kept
+            .last()
+            .is_some_and(|(previous, protected)| !protected && is_decorator(previous));
+        if follows_attribute && !protected[i] && line.trim().is_empty() {
+            let next = (i + 1..lines.len()).find(|&j| !lines[j].trim().is_empty());
+            if let Some(next) = next
+                && !protected[next]
+                && (is_decorator(&lines[next]) || is_proc_or_export(&lines[next]))
+            {
+                continue;
+            }
+        }
+        kept.push((line.clone(), protected[i]));
+    }
+
+    let rank = |attribute: &Attribute| {
+        config
+            .attribute_order
+            .iter()
+            .position(|name| *name == attribute.name)
+            .unwrap_or(config.attribute_order.len())
+    };
+
+    let mut result = Vec::with_capacity(kept.len());
+    let mut i = 0;
+    while i < kept.len() {
+        let (line, protected) = &kept[i];
+        if *protected || !is_decorator(line) {
+            result.push(line.clone());
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < kept.len() && !kept[i].1 && is_decorator(&kept[i].0) {
+            i += 1;
+        }
+        let run = &kept[start..i];
+        let parsed: Option<Vec<Attribute>> =
+            run.iter().map(|(line, _)| Attribute::parse(line)).collect();
+        match parsed {
+            Some(mut attributes) => {
+                attributes.sort_by_key(|attribute| rank(attribute));
+                let indent = &line[..line.len() - line.trim_start().len()];
+                result.extend(
+                    attributes
+                        .iter()
+                        .map(|attribute| format!("{indent}{}", attribute.render())),
+                );
+            }
+            None => result.extend(run.iter().map(|(line, _)| line.clone())),
+        }
+    }
+
+    result
+}
+
+/// Reports attributes separated from the procedure they decorate by empty lines or comments, and
+/// attributes that decorate no procedure at all. Attributes covered by a formatter directive are
+/// not checked.
+pub(crate) fn check_attributes(code: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = code.lines().collect();
+    let protected = protected_lines(&lines);
+    let mut diagnostics = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        // Only the last attribute of a run is checked against what follows it.
+        if protected[i]
+            || !is_decorator(line)
+            || lines.get(i + 1).is_some_and(|next| is_decorator(next))
+        {
+            continue;
+        }
+        let column = line.len() - line.trim_start().len() + 1;
+        let attribute = split_inline_comment(line).0.trim();
+
+        let mut j = i + 1;
+        let mut blank_line = None;
+        let mut comment_line = None;
+        while j < lines.len() && (lines[j].trim().is_empty() || is_comment(lines[j])) {
+            if lines[j].trim().is_empty() {
+                blank_line.get_or_insert(j);
+            } else {
+                comment_line.get_or_insert(j);
+            }
+            j += 1;
+        }
+
+        if !lines.get(j).is_some_and(|next| is_proc_or_export(next)) {
+            diagnostics.push(Diagnostic::error(
+                i + 1,
+                column,
+                format!("attribute `{attribute}` does not decorate a procedure"),
+            ));
+        } else if let Some(comment_line) = comment_line {
+            diagnostics.push(
+                Diagnostic::error(
+                    comment_line + 1,
+                    lines[comment_line].len() - lines[comment_line].trim_start().len() + 1,
+                    format!("comment between attribute `{attribute}` and its procedure"),
+                )
+                .with_help("move the comment above the attribute"),
+            );
+        } else if let Some(blank_line) = blank_line {
+            diagnostics.push(
+                Diagnostic::error(
+                    blank_line + 1,
+                    1,
+                    format!("empty line between attribute `{attribute}` and its procedure"),
+                )
+                .with_help("remove the empty line"),
+            );
+        }
+    }
+
+    diagnostics
+}
+
+/// Returns the number of locals of an `@locals(N)` attribute line.
+pub(crate) fn locals_attribute(line: &str) -> Option<&str> {
+    let count = line
+        .trim()
+        .strip_prefix("@locals(")?
+        .strip_suffix(')')?
+        .trim();
+    count.parse::<u16>().is_ok().then_some(count)
+}
+
+/// Splits a legacy declaration with a local count, such as `proc.foo.3`, into the declaration
+/// without the count and the count.
+fn split_locals_suffix(code: &str) -> Option<(&str, &str)> {
+    let body = code
+        .strip_prefix("proc.")
+        .or_else(|| code.strip_prefix("export."))?;
+    if body.contains("::") {
+        return None;
+    }
+    let (name, count) = body.rsplit_once('.')?;
+    (!name.is_empty() && !name.contains('.') && count.parse::<u16>().is_ok())
+        .then(|| (&code[..code.len() - count.len() - 1], count))
+}
+
+/// Returns true if the code is a legacy declaration without a local count, such as `proc.foo`.
+fn is_plain_legacy_declaration(code: &str) -> bool {
+    let body = code
+        .strip_prefix("proc.")
+        .or_else(|| code.strip_prefix("export."));
+    body.is_some_and(|name| !name.is_empty() && !name.contains(['.', ':', ' ']))
+}
+
+fn with_comment(code: String, comment: &str) -> String {
+    if comment.is_empty() {
+        code
+    } else {
+        format!("{code} {comment}")
+    }
+}
+
+/// Moves local counts between legacy declarations and `@locals` attributes. An attribute is
+/// placed directly above its declaration, below any doc comment and other attributes.
+pub(crate) fn rewrite_locals(lines: Vec<String>, style: LocalsStyle) -> Vec<String> {
+    if style == LocalsStyle::Preserve {
+        return lines;
+    }
+
+    let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let protected = protected_lines(&line_refs);
+    let mut result: Vec<String> = Vec::with_capacity(lines.len());
+
+    for (i, line) in lines.iter().enumerate() {
+        let (code, comment) = split_inline_comment(line);
+        let indent = &code[..code.len() - code.trim_start().len()];
+        let code = code.trim();
+        if protected[i] {
+            result.push(line.clone());
+            continue;
+        }
+
+        match style {
+            LocalsStyle::Attribute => {
+                let attributes = &result[result.len() - attribute_run(&result)..];
+                let has_locals = attributes
+                    .iter()
+                    .any(|line| line.trim().starts_with("@locals"));
+                if let Some((declaration, count)) = split_locals_suffix(code)
+                    && !has_locals
+                {
+                    result.push(format!("{indent}@locals({count})"));
+                    result.push(format!(
+                        "{indent}{}",
+                        with_comment(declaration.to_string(), comment)
+                    ));
+                    continue;
+                }
+            }
+            LocalsStyle::Suffix => {
+                if is_plain_legacy_declaration(code) {
+                    let first_attribute = result.len() - attribute_run(&result);
+                    let position = result[first_attribute..]
+                        .iter()
+                        .position(|line| locals_attribute(line).is_some());
+                    if let Some(offset) = position {
+                        let attribute = result.remove(first_attribute + offset);
+                        let count = locals_attribute(&attribute).unwrap_or_default();
+                        result.push(format!(
+                            "{indent}{}",
+                            with_comment(format!("{code}.{count}"), comment)
+                        ));
+                        continue;
+                    }
+                }
+            }
+            LocalsStyle::Preserve => {}
+        }
+        result.push(line.clone());
+    }
+
+    result
+}
+
+/// Returns the number of attribute lines at the end of `lines`.
+fn attribute_run(lines: &[String]) -> usize {
+    lines
+        .iter()
+        .rev()
+        .take_while(|line| is_decorator(line))
+        .count()
+}
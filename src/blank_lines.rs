@@ -0,0 +1,159 @@
+//! The configurable blank-line policy, applied to formatted output.
+
+use crate::{
+    ConstructType, config::BlankLineConfig, constants::ConstDecl, directives::protected_lines,
+    imports::Import, is_comment, is_decorator, is_proc_or_export, is_section_separator_comment,
+    leading_word, line_constructs, strip_inline_comment,
+};
+
+/// Returns true if the line is at module top level.
+fn is_top_level(line: &str) -> bool {
+    !line.starts_with(char::is_whitespace)
+}
+
+/// Returns the index of the first line from `start` on that is neither empty nor a comment.
+fn next_code_line(lines: &[&str], start: usize) -> Option<usize> {
+    (start..lines.len()).find(|&i| !lines[i].trim().is_empty() && !is_comment(lines[i]))
+}
+
+/// Returns true if the item starting at `start` is a procedure or program, possibly preceded by
+/// comments and attributes.
+fn starts_procedure(lines: &[&str], start: usize) -> bool {
+    let declaration = (start..lines.len()).find(|&i| {
+        let line = lines[i];
+        !line.trim().is_empty() && !is_comment(line) && !is_decorator(line)
+    });
+    declaration.is_some_and(|i| {
+        is_proc_or_export(lines[i]) || leading_word(strip_inline_comment(lines[i])) == Some("begin")
+    })
+}
+
+/// Marks the first and last separator comment of every run of consecutive comment lines that
+/// holds one, so that a multi-line section header is treated as a single separator.
+fn separator_bounds(lines: &[&str]) -> (Vec<bool>, Vec<bool>) {
+    let mut starts = vec![false; lines.len()];
+    let mut ends = vec![false; lines.len()];
+    let mut i = 0;
+    while i < lines.len() {
+        if !is_comment(lines[i]) {
+            i += 1;
+            continue;
+        }
+        let run_start = i;
+        while i < lines.len() && is_comment(lines[i]) {
+            i += 1;
+        }
+        let separators: Vec<usize> = (run_start..i)
+            .filter(|&j| is_section_separator_comment(lines[j]))
+            .collect();
+        if let (Some(&first), Some(&last)) = (separators.first(), separators.last()) {
+            starts[first] = true;
+            ends[last] = true;
+        }
+    }
+    (starts, ends)
+}
+
+/// Returns the number of empty lines wanted between the non-empty lines `a` and `b`, if a rule
+/// of the policy applies to them.
+fn desired_gap(
+    lines: &[&str],
+    separators: &(Vec<bool>, Vec<bool>),
+    a: usize,
+    b: usize,
+    config: &BlankLineConfig,
+) -> Option<usize> {
+    let (first, second) = (lines[a], lines[b]);
+    let (separator_starts, separator_ends) = separators;
+    let mut count = None;
+
+    if is_top_level(first) && is_top_level(second) {
+        let block_ends = |is_member: &dyn Fn(&str) -> bool| {
+            is_member(first) && !next_code_line(lines, b).is_some_and(|i| is_member(lines[i]))
+        };
+        if let Some(n) = config.after_imports
+            && block_ends(&|line| Import::parse(line).is_some())
+        {
+            count = Some(n);
+        }
+        if let Some(n) = config.after_constants
+            && block_ends(&|line| ConstDecl::parse(line).is_some())
+        {
+            count = Some(n);
+        }
+        if let Some(n) = config.between_procedures
+            && leading_word(strip_inline_comment(first)) == Some("end")
+            && starts_procedure(lines, b)
+        {
+            count = Some(n);
+        }
+        if let Some(n) = config.around_separators
+            && (separator_ends[a] || separator_starts[b])
+        {
+            count = Some(n);
+        }
+    }
+
+    if config.remove_after_open
+        && line_constructs(first)
+            .last()
+            .is_some_and(|construct| *construct != ConstructType::End)
+    {
+        count = Some(0);
+    }
+    if config.remove_before_end
+        && matches!(
+            line_constructs(second).first(),
+            Some(ConstructType::End | ConstructType::Else)
+        )
+    {
+        count = Some(0);
+    }
+    count
+}
+
+/// Returns the number of empty lines the policy wants between the non-empty lines `a` and `b`,
+/// if any of its rules applies to them.
+pub(crate) fn policy_gap(
+    lines: &[&str],
+    a: usize,
+    b: usize,
+    config: &BlankLineConfig,
+) -> Option<usize> {
+    desired_gap(lines, &separator_bounds(lines), a, b, config)
+}
+
+/// Rewrites the empty lines between every pair of consecutive non-empty lines according to the
+/// configured policy. Gaps not covered by any rule, or touching lines covered by a formatter
+/// directive, are kept as they are.
+pub(crate) fn apply_blank_line_policy(lines: Vec<String>, config: &BlankLineConfig) -> Vec<String> {
+    if *config == BlankLineConfig::default() {
+        return lines;
+    }
+
+    let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let protected = protected_lines(&line_refs);
+    let separators = separator_bounds(&line_refs);
+
+    let code_lines: Vec<usize> = (0..lines.len())
+        .filter(|&i| !lines[i].trim().is_empty())
+        .collect();
+    let (Some(&first), Some(&last)) = (code_lines.first(), code_lines.last()) else {
+        return lines;
+    };
+
+    let mut result: Vec<String> = lines[..first].to_vec();
+    for pair in code_lines.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        result.push(lines[a].clone());
+        let gap = b - a - 1;
+        let count = if protected[a..=b].iter().any(|&flag| flag) {
+            gap
+        } else {
+            desired_gap(&line_refs, &separators, a, b, config).unwrap_or(gap)
+        };
+        result.extend(std::iter::repeat_n(String::new(), count));
+    }
+    result.extend(lines[last..].iter().cloned());
+    result
+}
@@ -0,0 +1,264 @@
+//! Bracketed lists: word literals such as `push.[1, 2, 3, 4]` and the values of advice map
+//! entries such as `adv_map.KEY=[1, 2, 3, 4]`.
+
+use crate::{
+    INDENT, directives::protected_lines, is_comment, signatures::split_top_level,
+    split_inline_comment,
+};
+
+/// Returns the index of the bracket closing the one at `open`, skipping string literals.
+fn matching_bracket(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    for (i, c) in text[open..].char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the elements of a list given without its brackets, trimmed and without the empty
+/// element a trailing comma leaves.
+fn list_elements(inner: &str) -> Vec<String> {
+    split_top_level(inner)
+        .into_iter()
+        .map(|element| element.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|element| !element.is_empty())
+        .collect()
+}
+
+/// Rewrites every bracketed list in a piece of code as `[a, b, c]`, leaving string literals
+/// untouched. Lists that are never closed are kept as written.
+fn format_lists(code: &str) -> String {
+    let mut out = String::with_capacity(code.len());
+    let mut in_string = false;
+    let mut i = 0;
+
+    while let Some(c) = code[i..].chars().next() {
+        if c == '"' {
+            in_string = !in_string;
+        } else if c == '['
+            && !in_string
+            && let Some(close) = matching_bracket(code, i)
+        {
+            out.push_str(&format!(
+                "[{}]",
+                list_elements(&code[i + 1..close]).join(", ")
+            ));
+            i = close + 1;
+            continue;
+        }
+        out.push(c);
+        i += c.len_utf8();
+    }
+
+    out
+}
+
+/// An advice map entry: `adv_map.KEY=[...]`, optionally with an explicit key in parentheses
+/// after the name.
+struct AdviceMapEntry {
+    /// Everything up to the `=`, as in `adv_map.KEY(0x01)`.
+    head: String,
+    values: Vec<String>,
+    /// Inline comment following the entry, including the `#`.
+    comment: String,
+}
+
+impl AdviceMapEntry {
+    fn parse(line: &str) -> Option<Self> {
+        let (code, comment) = split_inline_comment(line.trim());
+        let code = code.trim();
+        if !code.starts_with("adv_map.") {
+            return None;
+        }
+
+        let open = code.find('[')?;
+        let head = code[..open].trim_end().strip_suffix('=')?;
+        if matching_bracket(code, open)? != code.len() - 1 {
+            return None;
+        }
+
+        Some(Self {
+            head: head.trim_end().to_string(),
+            values: list_elements(&code[open + 1..code.len() - 1]),
+            comment: comment.trim().to_string(),
+        })
+    }
+
+    fn comment_suffix(&self) -> String {
+        if self.comment.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", self.comment)
+        }
+    }
+
+    fn render(&self) -> String {
+        format!(
+            "{}=[{}]{}",
+            self.head,
+            self.values.join(", "),
+            self.comment_suffix()
+        )
+    }
+
+    /// Renders the entry with its values on indented lines between the brackets, as many on
+    /// each line as fit within `max_width`.
+    fn render_wrapped(&self, max_width: usize) -> Vec<String> {
+        let mut lines = vec![format!("{}=[", self.head)];
+        let mut current = String::new();
+        for (n, value) in self.values.iter().enumerate() {
+            let separator = if n + 1 == self.values.len() { "" } else { "," };
+            let candidate = if current.is_empty() {
+                format!("{INDENT}{value}{separator}")
+            } else {
+                format!("{current} {value}{separator}")
+            };
+            if !current.is_empty() && candidate.chars().count() > max_width {
+                lines.push(std::mem::take(&mut current));
+                current = format!("{INDENT}{value}{separator}");
+            } else {
+                current = candidate;
+            }
+        }
+        if !current.is_empty() {
+            lines.push(current);
+        }
+        lines.push(format!("]{}", self.comment_suffix()));
+        lines
+    }
+}
+
+/// Joins advice map entries whose values span several lines into a single line, so that the
+/// formatter can treat each entry as one line. A comment on the opening or closing line is kept
+/// after the joined entry; entries with other comments are left as written.
+pub(crate) fn join_advice_maps(lines: &[&str]) -> Vec<String> {
+    let protected = protected_lines(lines);
+    let mut result = Vec::with_capacity(lines.len());
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let code = split_inline_comment(line).0.trim();
+        let unclosed = !protected[i]
+            && code.starts_with("adv_map.")
+            && code
+                .find('[')
+                .is_some_and(|open| matching_bracket(code, open).is_none());
+
+        if unclosed {
+            let mut joined = code.to_string();
+            let mut comments = vec![split_inline_comment(line).1.trim()];
+            let mut end = i + 1;
+            while end < lines.len() && !protected[end] {
+                let (code, comment) = split_inline_comment(lines[end]);
+                joined.push(' ');
+                joined.push_str(code.trim());
+                comments.push(comment.trim());
+                end += 1;
+                let open = joined.find('[').unwrap_or_default();
+                if matching_bracket(&joined, open).is_some() {
+                    break;
+                }
+            }
+            // A comment on the opening or the closing line follows the joined entry. Entries with
+            // comments among their values, or on both of those lines, are left as written.
+            let (first, last) = (comments[0], comments[comments.len() - 1]);
+            let inner_comment = comments.len() > 2
+                && comments[1..comments.len() - 1]
+                    .iter()
+                    .any(|comment| !comment.is_empty());
+            let comment = match (first.is_empty(), last.is_empty()) {
+                (_, true) => Some(first),
+                (true, false) => Some(last),
+                (false, false) => None,
+            };
+            if !inner_comment
+                && let Some(comment) = comment
+                && let Some(mut entry) = AdviceMapEntry::parse(&joined)
+            {
+                entry.comment = comment.to_string();
+                result.push(entry.render());
+                i = end;
+                continue;
+            }
+        }
+
+        result.push(line.to_string());
+        i += 1;
+    }
+
+    result
+}
+
+/// Marks the lines continuing an advice map entry that spans several lines, which is the case
+/// for entries [`join_advice_maps`] leaves as written.
+pub(crate) fn advice_map_continuations(lines: &[&str]) -> Vec<bool> {
+    let mut continuations = vec![false; lines.len()];
+    let mut i = 0;
+
+    while i < lines.len() {
+        let code = split_inline_comment(lines[i]).0.trim();
+        let open = code.find('[').filter(|_| code.starts_with("adv_map."));
+        if let Some(open) = open
+            && matching_bracket(code, open).is_none()
+        {
+            let mut joined = code.to_string();
+            i += 1;
+            while i < lines.len() {
+                continuations[i] = true;
+                joined.push(' ');
+                joined.push_str(split_inline_comment(lines[i]).0.trim());
+                i += 1;
+                if matching_bracket(&joined, open).is_some() {
+                    break;
+                }
+            }
+            continue;
+        }
+        i += 1;
+    }
+
+    continuations
+}
+
+/// Formats bracketed lists with one space after each comma and no padding inside the brackets,
+/// and wraps advice map entries wider than `max_width` with their values on indented lines.
+pub(crate) fn format_bracketed_lists(lines: Vec<String>, max_width: usize) -> Vec<String> {
+    let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let protected = protected_lines(&line_refs);
+    let mut result = Vec::with_capacity(lines.len());
+
+    for (i, line) in lines.iter().enumerate() {
+        if protected[i] || is_comment(line) || !line.contains('[') {
+            result.push(line.clone());
+            continue;
+        }
+
+        let top_level = !line.starts_with(char::is_whitespace);
+        if top_level && let Some(entry) = AdviceMapEntry::parse(line) {
+            let rendered = entry.render();
+            if rendered.chars().count() > max_width && entry.values.len() > 1 {
+                result.extend(entry.render_wrapped(max_width));
+            } else {
+                result.push(rendered);
+            }
+            continue;
+        }
+
+        let (code, comment) = split_inline_comment(line);
+        result.push(format!("{}{comment}", format_lists(code)));
+    }
+
+    result
+}
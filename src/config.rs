@@ -0,0 +1,353 @@
+//! Formatter configuration.
+//!
+//! The configuration is read from a `masm-fmt.toml` file. Every setting is optional and the
+//! defaults reproduce the formatter's built-in behavior, so an empty file is a valid config.
+//!
+//! ```toml
+//! syntax = "modern"
+//!
+//! [imports]
+//! groups = ["std::*", "miden::*"]
+//!
+//! [constants]
+//! align = true
+//!
+//! [modules]
+//! libraries = { miden = "asm/miden" }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::Deserialize;
+
+/// Name of the configuration file looked up by [`FormatConfig::discover`].
+pub const CONFIG_FILE_NAME: &str = "masm-fmt.toml";
+
+/// Default maximum line width.
+pub const DEFAULT_MAX_WIDTH: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FormatConfig {
+    /// Maximum line width used when joining or wrapping lines. Lines are never broken up only
+    /// because they exceed it.
+    pub max_width: usize,
+    /// The syntax dialect accepted by the assembler the code targets. When set, declarations are
+    /// rewritten into this dialect and check mode reports procedures and re-exports in the other
+    /// one. When unset, check mode reports modules that mix both dialects.
+    pub syntax: Option<Syntax>,
+    pub imports: ImportConfig,
+    pub constants: ConstantConfig,
+    pub literals: LiteralConfig,
+    pub instructions: InstructionConfig,
+    pub procedures: ProcedureConfig,
+    pub reexports: ReexportConfig,
+    pub blank_lines: BlankLineConfig,
+    pub modules: ModuleConfig,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            max_width: DEFAULT_MAX_WIDTH,
+            syntax: None,
+            imports: ImportConfig::default(),
+            constants: ConstantConfig::default(),
+            literals: LiteralConfig::default(),
+            instructions: InstructionConfig::default(),
+            procedures: ProcedureConfig::default(),
+            reexports: ReexportConfig::default(),
+            blank_lines: BlankLineConfig::default(),
+            modules: ModuleConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImportConfig {
+    /// Module path patterns defining the import groups, in output order.
+    ///
+    /// A pattern ending in `*` matches every path starting with the text before it, any other
+    /// pattern matches a path exactly. Imports matching no pattern form a final group. When the
+    /// list is empty, each run of imports is sorted as a single group.
+    pub groups: Vec<String>,
+    /// Remove imports that are never referenced, and exact repeats of an earlier import.
+    pub remove_unused: bool,
+    /// Move imports found at module top level after the import block into the block.
+    pub hoist: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConstantConfig {
+    /// Column-align consecutive constant declarations: the `=` of `const X = 1`, and the value
+    /// after the attached `=` of `const.X=1`. Alignment groups are broken by empty lines, comments
+    /// and changes between the two syntaxes.
+    pub align: bool,
+    /// Use canonical spacing: none around `=` in the legacy syntax, one space in the modern
+    /// syntax, and one space around binary operators in the value.
+    pub normalize: bool,
+    /// Rewrite constant declarations into this syntax. Declarations keep their syntax if unset.
+    /// Must match [`FormatConfig::syntax`] if both are set.
+    pub syntax: Option<Syntax>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LiteralConfig {
+    /// Lowercase the `0x` prefix of hex literals and strip leading zeros from decimal literals.
+    pub normalize: bool,
+    /// Case of the digits of hex literals.
+    pub hex_case: HexCase,
+    /// Pad hex literals with leading zeros to a multiple of this many digits, as long as they
+    /// still fit in a single field element. `0` disables padding.
+    pub hex_pad: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HexCase {
+    #[default]
+    Preserve,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InstructionConfig {
+    /// Rewrite instructions with several spellings to a single one, e.g. `dup.0` to `dup` and
+    /// `swap.1` to `swap`. Only exact aliases are rewritten.
+    pub canonicalize: bool,
+    /// How to write pushes of several literal values.
+    pub push: PushStyle,
+    /// How to place instructions on lines.
+    pub layout: InstructionLayout,
+    /// How to format blocks written on a single line, such as `if.true push.1 else push.0 end`.
+    pub inline_blocks: InlineBlocks,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InlineBlocks {
+    /// Keep the block on its line, and indent the lines after it as if it were expanded.
+    #[default]
+    Keep,
+    /// Put every block keyword on its own line, with the instructions between them indented.
+    Expand,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InstructionLayout {
+    /// Keep instructions on the lines they were written on.
+    #[default]
+    Preserve,
+    /// Put every instruction on its own line.
+    OnePerLine,
+    /// Join runs of instruction lines up to the maximum width, never across comments, empty lines
+    /// or block keywords.
+    Packed,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushStyle {
+    /// Keep pushes as written.
+    #[default]
+    Preserve,
+    /// Merge adjacent pushes into a single `push.a.b.c`, up to the maximum number of immediates.
+    Merge,
+    /// Split multi-value pushes into one push per value.
+    Split,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProcedureConfig {
+    /// Where to declare the number of locals of a procedure.
+    pub locals: LocalsStyle,
+    /// Attribute names, without the `@`, in the order stacked attributes are written in.
+    /// Attributes not listed follow in their original order.
+    pub attribute_order: Vec<String>,
+    /// How to group procedures by visibility. Procedures move with their doc comments and
+    /// attributes, and program blocks are moved last.
+    pub order: ProcedureOrder,
+    /// Sort procedures by name within each group.
+    pub alphabetical: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProcedureOrder {
+    /// Keep procedures in the order they are written.
+    #[default]
+    Preserve,
+    /// Private procedures first, then exported ones.
+    HelpersFirst,
+    /// Exported procedures first, then private ones.
+    ExportsFirst,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LocalsStyle {
+    /// Keep local counts where they are written.
+    #[default]
+    Preserve,
+    /// Move the count of `proc.foo.3` into an `@locals(3)` attribute above the declaration.
+    Attribute,
+    /// Move the count of an `@locals(3)` attribute into the declaration, as in `proc.foo.3`. Only
+    /// declarations in the legacy syntax can hold a count.
+    Suffix,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReexportConfig {
+    /// Gather the re-exports of a module into one section, where the first one is, sorted by
+    /// exported name. Comments directly above a re-export move with it.
+    pub sort: bool,
+}
+
+/// Numbers of empty lines between top-level items. Unset counts keep the formatter's built-in
+/// behavior.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlankLineConfig {
+    /// Empty lines between the `end` of a procedure and the next procedure or program, including
+    /// its doc comment and attributes.
+    pub between_procedures: Option<usize>,
+    /// Empty lines after the last import of a block of imports.
+    pub after_imports: Option<usize>,
+    /// Empty lines after the last declaration of a block of constants.
+    pub after_constants: Option<usize>,
+    /// Empty lines before and after section separator comments such as `# ==== HELPERS ====`.
+    pub around_separators: Option<usize>,
+    /// Remove empty lines directly after a line opening a block, such as `proc.foo` or `if.true`.
+    pub remove_after_open: bool,
+    /// Remove empty lines directly before `end` and `else`.
+    pub remove_before_end: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModuleConfig {
+    /// Library namespaces and the directories holding their sources, used to read the constants of
+    /// imported modules. With `miden = "asm/miden"`, the module `miden::kernels::tx` is read from
+    /// `asm/miden/kernels/tx.masm`.
+    pub libraries: BTreeMap<String, PathBuf>,
+    /// Directory that relative library paths are resolved against. [`FormatConfig::from_file`]
+    /// sets it to the directory of the config file; when unset, paths are resolved against the
+    /// current directory.
+    #[serde(skip)]
+    pub base_dir: Option<PathBuf>,
+}
+
+impl ModuleConfig {
+    /// Reads the source of a module from the configured library directories.
+    pub fn resolve(&self, module_path: &str) -> Option<String> {
+        let mut segments = module_path.split("::");
+        let library = self.libraries.get(segments.next()?)?;
+        let mut file = match &self.base_dir {
+            Some(base_dir) => base_dir.join(library),
+            None => library.clone(),
+        };
+        for segment in segments {
+            file.push(segment);
+        }
+        file.set_extension("masm");
+        fs::read_to_string(file).ok()
+    }
+}
+
+/// A Miden Assembly syntax dialect.
+///
+/// The legacy dialect uses dotted forms such as `use.std::sys`, `export.foo` and `const.X=1`, the
+/// modern dialect uses `use std::sys`, `pub proc foo` and `const X = 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Syntax {
+    Legacy,
+    Modern,
+}
+
+impl Syntax {
+    /// The name of the dialect as written in the configuration.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Legacy => "legacy",
+            Self::Modern => "modern",
+        }
+    }
+}
+
+impl FromStr for Syntax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "legacy" => Ok(Self::Legacy),
+            "modern" => Ok(Self::Modern),
+            _ => Err(format!(
+                "unknown syntax `{s}`, expected `legacy` or `modern`"
+            )),
+        }
+    }
+}
+
+impl FormatConfig {
+    /// Parses a configuration from the contents of a `masm-fmt.toml` file.
+    pub fn from_toml(source: &str) -> io::Result<Self> {
+        let config: Self = toml::from_str(source)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Rejects settings that contradict each other.
+    fn validate(&self) -> io::Result<()> {
+        if let (Some(syntax), Some(constants)) = (self.syntax, self.constants.syntax)
+            && syntax != constants
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "`constants.syntax = \"{}\"` contradicts `syntax = \"{}\"`",
+                    constants.name(),
+                    syntax.name()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reads a configuration file. Relative library paths are resolved against the directory
+    /// holding it.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let source = fs::read_to_string(path)?;
+        let mut config = Self::from_toml(&source).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("invalid config {}: {err}", path.display()),
+            )
+        })?;
+        config.modules.base_dir = path.parent().map(Path::to_path_buf);
+        Ok(config)
+    }
+
+    /// Looks for a `masm-fmt.toml` file in `start` and its ancestors, returning the path of the
+    /// first one found.
+    pub fn discover(start: &Path) -> Option<PathBuf> {
+        start
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .find(|candidate| candidate.is_file())
+    }
+}
@@ -0,0 +1,473 @@
+//! Evaluation of constant declarations.
+//!
+//! Constant expressions are resolved against the other constants of the module and, for
+//! qualified names such as `u64::MAX`, against the constants of imported modules. Evaluation
+//! reports undefined names, cyclic definitions, values outside the field, and constants used as
+//! `u32` immediates whose value does not fit in a `u32`.
+
+use std::collections::HashMap;
+
+use crate::{
+    constants::{ConstDecl, Token, tokenize},
+    diagnostics::Diagnostic,
+    directives::protected_lines,
+    imports::Import,
+    is_comment, strip_inline_comment,
+};
+
+/// The modulus of the Miden field, 2^64 - 2^32 + 1.
+pub(crate) const FIELD_MODULUS: u64 = 0xFFFF_FFFF_0000_0001;
+
+/// Instructions whose immediates must fit in a `u32`, in addition to all `u32*` instructions.
+const U32_IMMEDIATE_OPS: &[&str] = &[
+    "mem_load",
+    "mem_loadw",
+    "mem_store",
+    "mem_storew",
+    "loc_load",
+    "loc_loadw",
+    "loc_store",
+    "loc_storew",
+    "locaddr",
+];
+
+/// Whether an instruction argument names a constant: an uppercase identifier such as `ADDR`,
+/// optionally qualified with a module alias as in `memory::ADDR`.
+fn is_constant_reference(argument: &str) -> bool {
+    let name =
+        argument.rsplit_once("::").map_or(
+            argument,
+            |(alias, name)| if alias.is_empty() { "" } else { name },
+        );
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Looks up the source of a module by its full path, e.g. `std::math::u64`.
+pub type ModuleResolver<'a> = dyn Fn(&str) -> Option<String> + 'a;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Felt(u64),
+    Word(Vec<Value>),
+    /// A value the evaluator does not model, or one depending on a module it cannot read.
+    Unknown,
+}
+
+struct Module {
+    lines: Vec<String>,
+    /// Constant name to the index of its declaration line and the declaration.
+    constants: HashMap<String, (usize, ConstDecl)>,
+    /// Import name to module path.
+    imports: HashMap<String, String>,
+}
+
+impl Module {
+    fn parse(source: &str) -> Self {
+        let lines: Vec<String> = source.lines().map(str::to_string).collect();
+        let mut constants = HashMap::new();
+        let mut imports = HashMap::new();
+        for (i, line) in lines.iter().enumerate() {
+            if let Some(decl) = ConstDecl::parse(line) {
+                constants.entry(decl.name.clone()).or_insert((i, decl));
+            } else if let Some(import) = Import::parse(line) {
+                imports.insert(import.name().to_string(), import.path.clone());
+            }
+        }
+        Self {
+            lines,
+            constants,
+            imports,
+        }
+    }
+}
+
+/// Key of a constant: the path of its module (empty for the module being checked) and its name.
+type ConstKey = (String, String);
+
+struct Evaluator<'a> {
+    modules: HashMap<String, Option<Module>>,
+    resolver: &'a ModuleResolver<'a>,
+    values: HashMap<ConstKey, Value>,
+    in_progress: Vec<ConstKey>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Where an expression being evaluated lives, for reporting.
+#[derive(Clone, Copy)]
+struct Site<'s> {
+    module: &'s str,
+    line: usize,
+    name: &'s str,
+}
+
+impl<'a> Evaluator<'a> {
+    fn new(source: &str, resolver: &'a ModuleResolver<'a>) -> Self {
+        let mut modules = HashMap::new();
+        modules.insert(String::new(), Some(Module::parse(source)));
+        Self {
+            modules,
+            resolver,
+            values: HashMap::new(),
+            in_progress: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn module(&mut self, path: &str) -> Option<&Module> {
+        if !self.modules.contains_key(path) {
+            let module = (self.resolver)(path).map(|source| Module::parse(&source));
+            self.modules.insert(path.to_string(), module);
+        }
+        self.modules.get(path).and_then(Option::as_ref)
+    }
+
+    /// Reports a diagnostic if it concerns the module being checked.
+    fn report(&mut self, site: Site, column_of: &str, message: String, error: bool) {
+        if !site.module.is_empty() {
+            return;
+        }
+        let line = &self.modules[""].as_ref().unwrap().lines[site.line];
+        let column = find_column(line, column_of);
+        let diagnostic = if error {
+            Diagnostic::error(site.line + 1, column, message)
+        } else {
+            Diagnostic::warning(site.line + 1, column, message)
+        };
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Evaluates the constant `name` of module `module`. Returns `None` if it is not defined.
+    fn constant(&mut self, module: &str, name: &str) -> Option<Value> {
+        let key = (module.to_string(), name.to_string());
+        if let Some(value) = self.values.get(&key) {
+            return Some(value.clone());
+        }
+        let (line, decl) = self.module(module)?.constants.get(name)?.clone();
+
+        if let Some(start) = self.in_progress.iter().position(|k| *k == key) {
+            let mut cycle: Vec<&str> = self.in_progress[start..]
+                .iter()
+                .map(|(_, name)| name.as_str())
+                .collect();
+            cycle.push(name);
+            let message = format!("cyclic constant definition: {}", cycle.join(" -> "));
+            let site = Site { module, line, name };
+            self.report(site, name, message, true);
+            return Some(Value::Unknown);
+        }
+
+        self.in_progress.push(key.clone());
+        let site = Site { module, line, name };
+        let value = match tokenize(&decl.value) {
+            Some(tokens) => {
+                let mut parser = Parser {
+                    tokens: &tokens,
+                    pos: 0,
+                };
+                let value = self.expression(&mut parser, site);
+                if parser.pos < tokens.len() {
+                    Value::Unknown
+                } else {
+                    value
+                }
+            }
+            None => Value::Unknown,
+        };
+        self.in_progress.pop();
+
+        self.values.insert(key, value.clone());
+        Some(value)
+    }
+
+    /// Resolves a possibly qualified constant name referenced from `site`. Names that cannot be
+    /// resolved are reported as errors if `undefined_is_error` is set, and as warnings otherwise.
+    fn reference(&mut self, site: Site, ident: &str, undefined_is_error: bool) -> Value {
+        match ident.rsplit_once("::") {
+            Some((alias, name)) => {
+                let Some(path) = self
+                    .module(site.module)
+                    .and_then(|module| module.imports.get(alias).cloned())
+                else {
+                    let message = format!("undefined module `{alias}` in `{ident}`");
+                    self.report(site, ident, message, undefined_is_error);
+                    return Value::Unknown;
+                };
+                if self.module(&path).is_none() {
+                    return Value::Unknown;
+                }
+                match self.constant(&path, name) {
+                    Some(value) => value,
+                    None => {
+                        let message = format!("undefined constant `{ident}`");
+                        self.report(site, ident, message, undefined_is_error);
+                        Value::Unknown
+                    }
+                }
+            }
+            None => match self.constant(site.module, ident) {
+                Some(value) => value,
+                None => {
+                    let message = format!("undefined constant `{ident}`");
+                    self.report(site, ident, message, undefined_is_error);
+                    Value::Unknown
+                }
+            },
+        }
+    }
+
+    fn expression(&mut self, parser: &mut Parser, site: Site) -> Value {
+        let mut value = self.term(parser, site);
+        while let Some(op @ ("+" | "-")) = parser.peek_op() {
+            parser.pos += 1;
+            let rhs = self.term(parser, site);
+            value = self.binary(op, value, rhs, site);
+        }
+        value
+    }
+
+    fn term(&mut self, parser: &mut Parser, site: Site) -> Value {
+        let mut value = self.unary(parser, site);
+        while let Some(op @ ("*" | "/" | "//")) = parser.peek_op() {
+            parser.pos += 1;
+            let rhs = self.unary(parser, site);
+            value = self.binary(op, value, rhs, site);
+        }
+        value
+    }
+
+    fn unary(&mut self, parser: &mut Parser, site: Site) -> Value {
+        if parser.peek_op() == Some("-") {
+            parser.pos += 1;
+            let value = self.unary(parser, site);
+            return self.binary("-", Value::Felt(0), value, site);
+        }
+        self.primary(parser, site)
+    }
+
+    fn primary(&mut self, parser: &mut Parser, site: Site) -> Value {
+        let Some(token) = parser.next() else {
+            return Value::Unknown;
+        };
+        match token {
+            Token::Number(text) => match parse_number(text) {
+                Some(value) if value < FIELD_MODULUS as u128 => Value::Felt(value as u64),
+                Some(_) => {
+                    let message = format!("literal `{text}` exceeds the field modulus");
+                    self.report(site, text, message, true);
+                    Value::Unknown
+                }
+                None => Value::Unknown,
+            },
+            Token::Ident(ident) => {
+                if parser.peek() == Some(&Token::Open('(')) {
+                    // A call such as `event("...")`, whose value is not modelled.
+                    parser.skip_group();
+                    Value::Unknown
+                } else {
+                    self.reference(site, ident, true)
+                }
+            }
+            Token::Open('(') => {
+                let value = self.expression(parser, site);
+                if parser.next() != Some(&Token::Close(')')) {
+                    return Value::Unknown;
+                }
+                value
+            }
+            Token::Open('[') => {
+                let mut elements = Vec::new();
+                loop {
+                    elements.push(self.expression(parser, site));
+                    match parser.next() {
+                        Some(Token::Comma) => continue,
+                        Some(Token::Close(']')) => break,
+                        _ => return Value::Unknown,
+                    }
+                }
+                Value::Word(elements)
+            }
+            _ => Value::Unknown,
+        }
+    }
+
+    fn binary(&mut self, op: &str, lhs: Value, rhs: Value, site: Site) -> Value {
+        let (Value::Felt(lhs), Value::Felt(rhs)) = (lhs, rhs) else {
+            return Value::Unknown;
+        };
+        let (lhs, rhs) = (lhs as i128, rhs as i128);
+        let modulus = FIELD_MODULUS as i128;
+        let exact = match op {
+            "+" => lhs + rhs,
+            "-" => lhs - rhs,
+            "*" => lhs * rhs,
+            "//" | "/" if rhs == 0 => {
+                let message = format!("division by zero in the value of `{}`", site.name);
+                self.report(site, site.name, message, true);
+                return Value::Unknown;
+            }
+            "//" => lhs / rhs,
+            // Field division: multiplication by the inverse of the divisor.
+            _ => return Value::Felt(mul_mod(lhs as u64, inverse(rhs as u64))),
+        };
+        if exact < 0 || exact >= modulus {
+            let message = format!(
+                "the value of `{}` overflows the field modulus and wraps around",
+                site.name
+            );
+            self.report(site, site.name, message, false);
+        }
+        Value::Felt(exact.rem_euclid(modulus) as u64)
+    }
+
+    /// Checks constants used as immediates of instructions that take a `u32`.
+    fn check_u32_immediates(&mut self, protected: &[bool]) {
+        let lines = self.modules[""].as_ref().unwrap().lines.clone();
+        for (i, line) in lines.iter().enumerate() {
+            if protected[i] || is_comment(line) || ConstDecl::parse(line).is_some() {
+                continue;
+            }
+            for instruction in strip_inline_comment(line).split_whitespace() {
+                let mut parts = instruction.split('.');
+                let op = parts.next().unwrap_or_default();
+                if !op.starts_with("u32") && !U32_IMMEDIATE_OPS.contains(&op) {
+                    continue;
+                }
+                for argument in parts {
+                    if !is_constant_reference(argument) {
+                        continue;
+                    }
+                    let site = Site {
+                        module: "",
+                        line: i,
+                        name: argument,
+                    };
+                    if let Value::Felt(value) = self.reference(site, argument, false)
+                        && value > u32::MAX as u64
+                    {
+                        let message = format!(
+                            "`{argument}` is used as a u32 immediate of `{op}`, but its value \
+                             {value} exceeds u32::MAX"
+                        );
+                        self.report(site, argument, message, false);
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct Parser<'t> {
+    tokens: &'t [Token],
+    pos: usize,
+}
+
+impl<'t> Parser<'t> {
+    fn peek(&self) -> Option<&'t Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn next(&mut self) -> Option<&'t Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    /// Skips a parenthesized group starting at the current token.
+    fn skip_group(&mut self) {
+        let mut depth = 0usize;
+        while let Some(token) = self.next() {
+            match token {
+                Token::Open(_) => depth += 1,
+                Token::Close(_) => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Parses a decimal, `0x` hexadecimal or `0b` binary literal. Underscores are allowed as digit
+/// separators.
+pub(crate) fn parse_number(text: &str) -> Option<u128> {
+    let text = text.replace('_', "");
+    let (digits, radix) = if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        (hex, 16)
+    } else if let Some(binary) = text.strip_prefix("0b") {
+        (binary, 2)
+    } else {
+        (text.as_str(), 10)
+    };
+    u128::from_str_radix(digits, radix).ok()
+}
+
+fn mul_mod(a: u64, b: u64) -> u64 {
+    ((a as u128 * b as u128) % FIELD_MODULUS as u128) as u64
+}
+
+fn inverse(value: u64) -> u64 {
+    // Fermat's little theorem: value^(p - 2) is the inverse of value.
+    let mut result = 1;
+    let mut base = value;
+    let mut exponent = FIELD_MODULUS - 2;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base);
+        }
+        base = mul_mod(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// Returns the 1-based column of `needle` in the value part of a line, or of the first
+/// non-whitespace character if it is not found.
+fn find_column(line: &str, needle: &str) -> usize {
+    let value_start = line.find('=').map_or(0, |i| i + 1);
+    let offset = line[value_start..]
+        .find(needle)
+        .map(|i| value_start + i)
+        .or_else(|| line.find(needle))
+        .unwrap_or(line.len() - line.trim_start().len());
+    line[..offset].chars().count() + 1
+}
+
+/// Evaluates every constant of a module and checks the constants used as `u32` immediates.
+pub(crate) fn check_constants(source: &str, resolver: &ModuleResolver) -> Vec<Diagnostic> {
+    let mut evaluator = Evaluator::new(source, resolver);
+    let protected = {
+        let lines: Vec<&str> = source.lines().collect();
+        protected_lines(&lines)
+    };
+
+    let mut names: Vec<(usize, String)> = evaluator.modules[""]
+        .as_ref()
+        .unwrap()
+        .constants
+        .iter()
+        .map(|(name, (line, _))| (*line, name.clone()))
+        .collect();
+    names.sort();
+    for (_, name) in names {
+        evaluator.constant("", &name);
+    }
+    evaluator.check_u32_immediates(&protected);
+
+    let mut diagnostics = evaluator.diagnostics;
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    diagnostics.dedup();
+    diagnostics
+}
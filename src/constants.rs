@@ -0,0 +1,320 @@
+//! Constant declarations: `const.NAME=VALUE` in the legacy syntax, `const NAME = VALUE` in the
+//! modern one.
+
+use crate::{
+    config::{ConstantConfig, Syntax},
+    directives::protected_lines,
+    strip_inline_comment,
+};
+
+/// A token of a constant expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    /// A decimal or `0x`-prefixed hexadecimal literal, as written.
+    Number(String),
+    /// A constant name, possibly qualified with a module path (`u64::MAX`).
+    Ident(String),
+    /// A string literal, including its quotes.
+    Str(String),
+    /// One of `+`, `-`, `*`, `/` or `//`.
+    Op(&'static str),
+    /// `(` or `[`.
+    Open(char),
+    /// `)` or `]`.
+    Close(char),
+    Comma,
+}
+
+/// Splits a constant expression into tokens. Returns `None` if the expression contains anything
+/// the tokenizer does not understand, in which case it should be left as written.
+pub(crate) fn tokenize(expr: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    let take_while = |start: usize, pred: &dyn Fn(char) -> bool| {
+        let mut end = start;
+        while end < chars.len() && pred(chars[end]) {
+            end += 1;
+        }
+        end
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '0'..='9' => {
+                let end = take_while(i, &|c| c.is_ascii_alphanumeric() || c == '_');
+                tokens.push(Token::Number(chars[i..end].iter().collect()));
+                i = end;
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = i;
+                loop {
+                    end = take_while(end, &|c| c.is_ascii_alphanumeric() || c == '_');
+                    if chars.get(end) == Some(&':') && chars.get(end + 1) == Some(&':') {
+                        end += 2;
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Ident(chars[i..end].iter().collect()));
+                i = end;
+            }
+            '"' => {
+                let end = take_while(i + 1, &|c| c != '"');
+                if end >= chars.len() {
+                    return None;
+                }
+                tokens.push(Token::Str(chars[i..=end].iter().collect()));
+                i = end + 1;
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                tokens.push(Token::Op("//"));
+                i += 2;
+            }
+            '+' | '-' | '*' | '/' => {
+                tokens.push(Token::Op(match c {
+                    '+' => "+",
+                    '-' => "-",
+                    '*' => "*",
+                    _ => "/",
+                }));
+                i += 1;
+            }
+            '(' | '[' => {
+                tokens.push(Token::Open(c));
+                i += 1;
+            }
+            ')' | ']' => {
+                tokens.push(Token::Close(c));
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            _ => return None,
+        }
+    }
+
+    Some(tokens)
+}
+
+/// Renders tokens with canonical spacing: one space around binary operators, one space after
+/// commas and no other spaces.
+pub(crate) fn render_tokens(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut previous: Option<&Token> = None;
+
+    for token in tokens {
+        match token {
+            Token::Op(op) => {
+                let is_unary = matches!(
+                    previous,
+                    None | Some(Token::Op(_)) | Some(Token::Open(_)) | Some(Token::Comma)
+                );
+                if is_unary {
+                    out.push_str(op);
+                } else {
+                    out.push(' ');
+                    out.push_str(op);
+                    out.push(' ');
+                }
+            }
+            Token::Number(text) | Token::Ident(text) | Token::Str(text) => out.push_str(text),
+            Token::Open(c) | Token::Close(c) => out.push(*c),
+            Token::Comma => out.push_str(", "),
+        }
+        previous = Some(token);
+    }
+
+    out
+}
+
+/// Returns the expression with canonical spacing, or the expression unchanged if it cannot be
+/// tokenized.
+pub(crate) fn normalize_expression(expr: &str) -> String {
+    match tokenize(expr) {
+        Some(tokens) => render_tokens(&tokens),
+        None => expr.to_string(),
+    }
+}
+
+/// A single constant declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ConstDecl {
+    pub syntax: Syntax,
+    pub name: String,
+    /// The value expression as written, trimmed.
+    pub value: String,
+    /// Inline comment following the declaration, including the `#`.
+    pub comment: Option<String>,
+}
+
+impl ConstDecl {
+    pub fn parse(line: &str) -> Option<Self> {
+        let trimmed = line.trim();
+        let code = strip_inline_comment(trimmed);
+        let (syntax, body) = if let Some(body) = code.strip_prefix("const.") {
+            (Syntax::Legacy, body)
+        } else if let Some(body) = code.strip_prefix("const")
+            && body.starts_with(char::is_whitespace)
+        {
+            (Syntax::Modern, body)
+        } else {
+            return None;
+        };
+
+        let (name, value) = body.split_once('=')?;
+        let (name, value) = (name.trim(), value.trim());
+        if name.is_empty() || value.is_empty() || name.contains(char::is_whitespace) {
+            return None;
+        }
+        let comment = trimmed[code.len()..]
+            .trim()
+            .strip_prefix('#')
+            .map(|comment| format!("#{comment}"));
+
+        Some(Self {
+            syntax,
+            name: name.to_string(),
+            value: value.to_string(),
+            comment,
+        })
+    }
+
+    /// Renders the declaration with canonical spacing: `const.NAME=VALUE` in the legacy syntax and
+    /// `const NAME = VALUE` in the modern one.
+    pub fn render(&self) -> String {
+        let mut line = match self.syntax {
+            Syntax::Legacy => format!("{}={}", self.head(), self.value),
+            Syntax::Modern => format!("{} = {}", self.head(), self.value),
+        };
+        if let Some(comment) = &self.comment {
+            line.push(' ');
+            line.push_str(comment);
+        }
+        line
+    }
+
+    /// The declaration up to and including the name, e.g. `const.NAME`.
+    fn head(&self) -> String {
+        match self.syntax {
+            Syntax::Legacy => format!("const.{}", self.name),
+            Syntax::Modern => format!("const {}", self.name),
+        }
+    }
+}
+
+/// Applies the configured constant formatting: canonical spacing and syntax first, then
+/// alignment.
+pub(crate) fn format_constants(lines: Vec<String>, config: &ConstantConfig) -> Vec<String> {
+    let lines = if config.normalize || config.syntax.is_some() {
+        normalize_constants(&lines, config)
+    } else {
+        lines
+    };
+    if config.align {
+        align_constant_blocks(&lines)
+    } else {
+        lines
+    }
+}
+
+/// Rewrites constant declarations with canonical spacing around `=` and inside the value
+/// expression, converting them to the configured syntax if one is set.
+fn normalize_constants(lines: &[String], config: &ConstantConfig) -> Vec<String> {
+    let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let protected = protected_lines(&line_refs);
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let decl = if protected[i] {
+                None
+            } else {
+                ConstDecl::parse(line)
+            };
+            let Some(mut decl) = decl else {
+                return line.clone();
+            };
+            if let Some(syntax) = config.syntax {
+                decl.syntax = syntax;
+            }
+            if config.normalize {
+                decl.value = normalize_expression(&decl.value);
+            }
+            let indent = &line[..line.len() - line.trim_start().len()];
+            format!("{indent}{}", decl.render())
+        })
+        .collect()
+}
+
+/// Column-aligns consecutive constant declarations: the `=` in the spaced syntax, and the values
+/// after `=` in the dotted syntax. Alignment groups are broken by any line that is not a constant
+/// declaration, including empty lines and comments, by lines covered by a formatter directive, and
+/// where the syntax changes, since the two syntaxes put their values in different columns.
+fn align_constant_blocks(lines: &[String]) -> Vec<String> {
+    let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let protected = protected_lines(&line_refs);
+    let mut result = Vec::with_capacity(lines.len());
+    let mut group: Vec<(&str, ConstDecl)> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let decl = if protected[i] {
+            None
+        } else {
+            ConstDecl::parse(line)
+        };
+        match decl {
+            Some(decl) => {
+                if group
+                    .last()
+                    .is_some_and(|(_, last)| last.syntax != decl.syntax)
+                {
+                    flush_aligned_group(&mut group, &mut result);
+                }
+                group.push((line, decl));
+            }
+            None => {
+                flush_aligned_group(&mut group, &mut result);
+                result.push(line.clone());
+            }
+        }
+    }
+    flush_aligned_group(&mut group, &mut result);
+
+    result
+}
+
+fn flush_aligned_group(group: &mut Vec<(&str, ConstDecl)>, result: &mut Vec<String>) {
+    if group.len() < 2 {
+        result.extend(group.drain(..).map(|(line, _)| line.to_string()));
+        return;
+    }
+
+    let head_width = group
+        .iter()
+        .map(|(_, decl)| decl.head().chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for (line, decl) in group.drain(..) {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let head = decl.head();
+        let padding = " ".repeat(head_width - head.chars().count());
+        // The dotted syntax keeps `=` attached to the name, so its values are aligned instead.
+        let mut aligned = match decl.syntax {
+            Syntax::Legacy => format!("{indent}{head}={padding}{}", decl.value),
+            Syntax::Modern => format!("{indent}{head}{padding} = {}", decl.value),
+        };
+        if let Some(comment) = &decl.comment {
+            aligned.push(' ');
+            aligned.push_str(comment);
+        }
+        result.push(aligned);
+    }
+}
@@ -0,0 +1,226 @@
+//! Findings reported by the formatter's analyses, and their rendering for people and tools.
+
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => f.write_str("error"),
+            Self::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+/// A single finding, pointing at a location in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+    pub message: String,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn warning(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            line,
+            column,
+            message: message.into(),
+            help: None,
+        }
+    }
+
+    pub fn error(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            ..Self::warning(line, column, message)
+        }
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, self.severity, self.message
+        )
+    }
+}
+
+/// How diagnostics are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiagnosticFormat {
+    /// The location, the source line with the offending part underlined, and the help message.
+    #[default]
+    Plain,
+    /// Like `Plain`, with ANSI colors for terminals.
+    Colored,
+    /// One JSON object per diagnostic, on a single line.
+    Json,
+}
+
+impl FromStr for DiagnosticFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(Self::Plain),
+            "colored" => Ok(Self::Colored),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "unknown diagnostic format `{s}`, expected `plain`, `colored` or `json`"
+            )),
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const BOLD_RED: &str = "\x1b[1;31m";
+const BOLD_YELLOW: &str = "\x1b[1;33m";
+const BOLD_BLUE: &str = "\x1b[1;34m";
+
+/// Wraps `text` in an ANSI color sequence if `colored` is set.
+fn paint(text: &str, color: &str, colored: bool) -> String {
+    if colored {
+        format!("{color}{text}{RESET}")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Escapes a string for use inside a JSON string literal.
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => out.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl Diagnostic {
+    /// Renders the diagnostic for the file at `path` whose contents are `source`.
+    ///
+    /// The plain and colored formats look like compiler output:
+    ///
+    /// ```text
+    /// error: `proc.foo` is never closed with `end`
+    ///  --> src/foo.masm:3:1
+    ///   |
+    /// 3 | proc.foo
+    ///   | ^^^^^^^^
+    ///   |
+    ///   = help: the block was left unformatted
+    /// ```
+    ///
+    /// The JSON format is a single line without a trailing newline; the others end with one.
+    pub fn render(&self, path: &str, source: &str, format: DiagnosticFormat) -> String {
+        let source_line = self
+            .line
+            .checked_sub(1)
+            .and_then(|index| source.lines().nth(index));
+        match format {
+            DiagnosticFormat::Json => self.render_json(path, source_line),
+            DiagnosticFormat::Plain => self.render_snippet(path, source_line, false),
+            DiagnosticFormat::Colored => self.render_snippet(path, source_line, true),
+        }
+    }
+
+    fn render_json(&self, path: &str, source_line: Option<&str>) -> String {
+        let optional = |value: Option<&str>| value.map_or("null".to_string(), json_string);
+        format!(
+            concat!(
+                "{{\"file\":{},\"line\":{},\"column\":{},\"severity\":{},",
+                "\"message\":{},\"help\":{},\"source\":{}}}",
+            ),
+            json_string(path),
+            self.line,
+            self.column,
+            json_string(&self.severity.to_string()),
+            json_string(&self.message),
+            optional(self.help.as_deref()),
+            optional(source_line),
+        )
+    }
+
+    fn render_snippet(&self, path: &str, source_line: Option<&str>, colored: bool) -> String {
+        let severity_color = match self.severity {
+            Severity::Error => BOLD_RED,
+            Severity::Warning => BOLD_YELLOW,
+        };
+        let number = self.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let bar = paint("|", BOLD_BLUE, colored);
+
+        let mut out = format!(
+            "{}{}\n{gutter}{} {path}:{}:{}\n",
+            paint(&self.severity.to_string(), severity_color, colored),
+            paint(&format!(": {}", self.message), BOLD, colored),
+            paint("-->", BOLD_BLUE, colored),
+            self.line,
+            self.column,
+        );
+
+        if let Some(source_line) = source_line {
+            // Underline the word starting at the reported column.
+            let offset = self.column.saturating_sub(1);
+            let width = source_line
+                .chars()
+                .skip(offset)
+                .take_while(|c| !c.is_whitespace())
+                .count()
+                .max(1);
+            let padding: String = source_line
+                .chars()
+                .take(offset)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            out.push_str(&format!("{gutter} {bar}\n"));
+            out.push_str(&format!(
+                "{} {bar} {source_line}\n",
+                paint(&number, BOLD_BLUE, colored)
+            ));
+            out.push_str(&format!(
+                "{gutter} {bar} {padding}{}\n",
+                paint(&"^".repeat(width), severity_color, colored)
+            ));
+        }
+
+        if let Some(help) = &self.help {
+            out.push_str(&format!("{gutter} {bar}\n"));
+            out.push_str(&format!(
+                "{gutter} {} {}: {help}\n",
+                paint("=", BOLD_BLUE, colored),
+                paint("help", BOLD, colored),
+            ));
+        }
+
+        out
+    }
+}
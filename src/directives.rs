@@ -0,0 +1,111 @@
+//! Formatter directives embedded in comments.
+//!
+//! Three directives are recognised, each written as a comment on its own line:
+//!
+//! - `# masm-fmt: off` starts a region that is emitted byte-for-byte.
+//! - `# masm-fmt: on` ends that region.
+//! - `# masm-fmt: skip` leaves the next item untouched. If the next item is a procedure (including
+//!   any comments and attributes above it) or another block, the whole block up to its matching
+//!   `end` is skipped, otherwise only the next line is.
+
+use crate::{ConstructType, is_comment, is_decorator, line_constructs};
+
+const DIRECTIVE_PREFIX: &str = "masm-fmt:";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Directive {
+    Off,
+    On,
+    Skip,
+}
+
+/// Parses a formatter directive out of a comment line, if it contains one.
+pub(crate) fn parse_directive(line: &str) -> Option<Directive> {
+    let comment = line.trim().strip_prefix('#')?;
+    let comment = comment.strip_prefix('!').unwrap_or(comment).trim_start();
+    match comment.strip_prefix(DIRECTIVE_PREFIX)?.trim() {
+        "off" => Some(Directive::Off),
+        "on" => Some(Directive::On),
+        "skip" => Some(Directive::Skip),
+        _ => None,
+    }
+}
+
+/// Returns true if the line is any formatter directive.
+pub(crate) fn is_directive(line: &str) -> bool {
+    parse_directive(line).is_some()
+}
+
+/// Computes which lines must be emitted exactly as written.
+///
+/// The directive lines themselves are not protected; they are formatted like any other comment.
+pub(crate) fn protected_lines(lines: &[&str]) -> Vec<bool> {
+    let mut protected = vec![false; lines.len()];
+    let mut i = 0;
+
+    while i < lines.len() {
+        match parse_directive(lines[i]) {
+            Some(Directive::Off) => {
+                i += 1;
+                while i < lines.len() && parse_directive(lines[i]) != Some(Directive::On) {
+                    protected[i] = true;
+                    i += 1;
+                }
+            }
+            Some(Directive::Skip) => {
+                let end = skip_target_end(lines, i + 1);
+                for flag in protected.iter_mut().take(end).skip(i + 1) {
+                    *flag = true;
+                }
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    protected
+}
+
+/// Returns the index one past the last line covered by a `skip` directive whose next line is
+/// `start`. Blank lines between the directive and its target are covered as well, and so are
+/// comment lines directly above it.
+fn skip_target_end(lines: &[&str], start: usize) -> usize {
+    let mut i = start;
+    while i < lines.len() && lines[i].trim().is_empty() {
+        i += 1;
+    }
+    // Doc comments and attributes belong to the procedure they decorate.
+    while i < lines.len()
+        && (is_decorator(lines[i]) || (is_comment(lines[i]) && !is_directive(lines[i])))
+    {
+        i += 1;
+    }
+    if i >= lines.len() {
+        return lines.len();
+    }
+
+    let opens_block = matches!(
+        line_constructs(lines[i]).first(),
+        Some(construct) if *construct != ConstructType::End && *construct != ConstructType::Else
+    );
+    if !opens_block {
+        return i + 1;
+    }
+
+    let mut depth = 0usize;
+    for (j, line) in lines.iter().enumerate().skip(i) {
+        for construct in line_constructs(line) {
+            match construct {
+                ConstructType::End => depth = depth.saturating_sub(1),
+                ConstructType::Else => {}
+                _ => depth += 1,
+            }
+        }
+        if depth == 0 {
+            return j + 1;
+        }
+    }
+    lines.len()
+}
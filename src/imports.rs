@@ -0,0 +1,422 @@
+//! Sorting and grouping of the import section at the top of a module.
+
+use std::collections::{HashMap, HashSet};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{
+    ConstructType,
+    config::{ImportConfig, Syntax},
+    constants::ConstDecl,
+    diagnostics::Diagnostic,
+    directives::{Directive, is_directive, parse_directive, protected_lines},
+    is_comment, line_constructs, strip_inline_comment,
+};
+
+/// Matches the first segment of a `::`-separated path, e.g. `u64` in `exec.u64::wrapping_add`.
+static PATH_ROOT_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|[^A-Za-z0-9_:])([A-Za-z_][A-Za-z0-9_]*)::").unwrap());
+
+/// Matches a string literal, such as the event name in `emit.event("miden::note#created")`.
+static STRING_LITERAL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#""(?:[^"\\]|\\.)*""#).unwrap());
+
+/// A single import statement, in either the dotted (`use.std::sys`) or the spaced
+/// (`use std::sys`) syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Import {
+    pub syntax: Syntax,
+    /// Full module path, e.g. `std::math::u64`.
+    pub path: String,
+    /// Alias given with `->`, if any.
+    pub alias: Option<String>,
+    /// Inline comment following the statement, including the `#`.
+    pub comment: Option<String>,
+    /// The statement in canonical form: no spaces around `::` and `->`.
+    pub line: String,
+}
+
+impl Import {
+    pub fn parse(line: &str) -> Option<Self> {
+        let trimmed = line.trim();
+        let code = strip_inline_comment(trimmed);
+        let (syntax, body) = if let Some(body) = code.strip_prefix("use.") {
+            (Syntax::Legacy, body)
+        } else if let Some(body) = code.strip_prefix("use")
+            && body.starts_with(char::is_whitespace)
+        {
+            (Syntax::Modern, body)
+        } else {
+            return None;
+        };
+
+        let (path, alias) = match body.split_once("->") {
+            Some((path, alias)) => (path, Some(alias.trim().to_string())),
+            None => (body, None),
+        };
+        let path = path
+            .split("::")
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("::");
+        if path.is_empty() || alias.as_deref() == Some("") {
+            return None;
+        }
+        let comment = trimmed[code.len()..]
+            .trim()
+            .strip_prefix('#')
+            .map(|comment| format!("#{comment}"));
+
+        let mut import = Self {
+            syntax,
+            path,
+            alias,
+            comment,
+            line: String::new(),
+        };
+        import.line = import.render();
+        Some(import)
+    }
+
+    /// Renders the statement in canonical form.
+    pub fn render(&self) -> String {
+        let mut line = match self.syntax {
+            Syntax::Legacy => format!("use.{}", self.path),
+            Syntax::Modern => format!("use {}", self.path),
+        };
+        if let Some(alias) = &self.alias {
+            line.push_str("->");
+            line.push_str(alias);
+        }
+        if let Some(comment) = &self.comment {
+            line.push(' ');
+            line.push_str(comment);
+        }
+        line
+    }
+
+    /// The name the module is referred to by: the alias if one is given, otherwise the last path
+    /// segment.
+    pub fn name(&self) -> &str {
+        match &self.alias {
+            Some(alias) => alias,
+            None => self.path.rsplit("::").next().unwrap_or(&self.path),
+        }
+    }
+}
+
+/// Result of checking the imports of a module.
+#[derive(Debug, Default)]
+pub(crate) struct ImportAnalysis {
+    pub diagnostics: Vec<Diagnostic>,
+    /// Indices of import lines that can be removed without changing the module: imports that are
+    /// never referenced and exact repeats of an earlier import.
+    pub removable: Vec<usize>,
+}
+
+/// Finds imports that are never referenced and imports that repeat a path or alias.
+pub(crate) fn analyze_imports(lines: &[&str]) -> ImportAnalysis {
+    let mut imports = Vec::new();
+    let mut referenced = HashSet::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(import) = Import::parse(line) {
+            imports.push((i, import));
+        } else if !is_comment(line) {
+            let code = STRING_LITERAL_REGEX.replace_all(strip_inline_comment(line), "\"\"");
+            for captures in PATH_ROOT_REGEX.captures_iter(&code) {
+                referenced.insert(captures[1].to_string());
+            }
+        }
+    }
+
+    let mut analysis = ImportAnalysis::default();
+    let mut seen_paths: HashMap<&str, &Import> = HashMap::new();
+    let mut seen_names: HashMap<&str, &Import> = HashMap::new();
+
+    for (i, import) in &imports {
+        let column = lines[*i].len() - lines[*i].trim_start().len() + 1;
+
+        if let Some(first) = seen_paths.get(import.path.as_str()) {
+            if first.name() == import.name() {
+                analysis.diagnostics.push(
+                    Diagnostic::warning(
+                        i + 1,
+                        column,
+                        format!("duplicate import of `{}`", import.path),
+                    )
+                    .with_help("remove this import"),
+                );
+                analysis.removable.push(*i);
+                continue;
+            }
+            analysis.diagnostics.push(Diagnostic::warning(
+                i + 1,
+                column,
+                format!(
+                    "`{}` is imported more than once under different names",
+                    import.path
+                ),
+            ));
+        } else if let Some(first) = seen_names.get(import.name()) {
+            analysis.diagnostics.push(
+                Diagnostic::error(
+                    i + 1,
+                    column,
+                    format!(
+                        "the name `{}` is imported for both `{}` and `{}`",
+                        import.name(),
+                        first.path,
+                        import.path
+                    ),
+                )
+                .with_help("give one of the imports a different alias with `->`"),
+            );
+        }
+        seen_paths.entry(&import.path).or_insert(import);
+        seen_names.entry(import.name()).or_insert(import);
+
+        if !referenced.contains(import.name()) {
+            analysis.diagnostics.push(
+                Diagnostic::warning(i + 1, column, format!("unused import `{}`", import.path))
+                    .with_help("remove this import"),
+            );
+            analysis.removable.push(*i);
+        }
+    }
+
+    for stray in find_stray_imports(lines) {
+        let line = lines[stray.index];
+        let column = line.len() - line.trim_start().len() + 1;
+        let diagnostic = if stray.blocked {
+            Diagnostic::warning(
+                stray.index + 1,
+                column,
+                "import after the import block cannot be hoisted across a `masm-fmt: off` region",
+            )
+            .with_help("move the import to the top of the module by hand")
+        } else {
+            Diagnostic::warning(
+                stray.index + 1,
+                column,
+                "import is not part of the import block at the top of the module",
+            )
+            .with_help("move it to the import block, or set `imports.hoist = true`")
+        };
+        analysis.diagnostics.push(diagnostic);
+    }
+
+    analysis.removable.sort_unstable();
+    analysis
+}
+
+/// An import that appears at module top level after the import block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct StrayImport {
+    pub index: usize,
+    /// Whether a top-level `masm-fmt: off` region lies between the import block and this import.
+    pub blocked: bool,
+}
+
+/// Returns the index of the first line after the comment lines at the start of the module, if
+/// they are followed by an empty line. Comments directly above the first item belong to it.
+fn header_end(lines: &[&str]) -> usize {
+    let comments = lines
+        .iter()
+        .take_while(|line| is_comment(line) && !is_directive(line))
+        .count();
+    match lines.get(comments) {
+        Some(line) if comments > 0 && line.trim().is_empty() => comments + 1,
+        _ => 0,
+    }
+}
+
+/// Returns the index of the first line after the import block.
+fn import_section_end(lines: &[&str]) -> usize {
+    process_import_section(lines, &ImportConfig::default()).1
+}
+
+/// Finds imports at module top level that come after the import block. Imports inside a region
+/// covered by a formatter directive are left alone and not reported.
+pub(crate) fn find_stray_imports(lines: &[&str]) -> Vec<StrayImport> {
+    let section_end = import_section_end(lines);
+    let protected = protected_lines(lines);
+    let mut stray_imports = Vec::new();
+    let mut depth = 0usize;
+    let mut blocked = false;
+
+    for (i, line) in lines.iter().enumerate().skip(section_end) {
+        // Off regions inside procedure bodies do not lie between top-level items.
+        if depth == 0 && parse_directive(line) == Some(Directive::Off) {
+            blocked = true;
+        }
+        let depth_before = depth;
+        for construct in line_constructs(line) {
+            match construct {
+                ConstructType::End => depth = depth.saturating_sub(1),
+                ConstructType::Else => {}
+                _ => depth += 1,
+            }
+        }
+        if depth_before == 0 && !protected[i] && Import::parse(line).is_some() {
+            stray_imports.push(StrayImport { index: i, blocked });
+        }
+    }
+
+    stray_imports
+}
+
+/// Moves stray imports into the import block, where they are sorted with the other imports.
+/// Imports that would have to cross a `masm-fmt: off` region stay where they are.
+pub(crate) fn hoist_stray_imports<'a>(lines: &[&'a str]) -> Vec<&'a str> {
+    let hoisted: Vec<usize> = find_stray_imports(lines)
+        .into_iter()
+        .filter(|stray| !stray.blocked)
+        .map(|stray| stray.index)
+        .collect();
+    if hoisted.is_empty() {
+        return lines.to_vec();
+    }
+
+    // Insert right after the last import of the block, so that the hoisted imports join its last
+    // run rather than following any comments below it. Without an import block, insert them after
+    // the module's header comment.
+    let section_end = import_section_end(lines);
+    let insert_at = (0..section_end)
+        .rev()
+        .find(|&i| Import::parse(lines[i]).is_some())
+        .map_or_else(|| header_end(lines).max(section_end), |i| i + 1);
+
+    let mut result = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        if i == insert_at {
+            result.extend(hoisted.iter().map(|&index| lines[index]));
+        }
+        if hoisted.binary_search(&i).is_err() {
+            result.push(*line);
+        }
+    }
+    if insert_at == lines.len() {
+        result.extend(hoisted.iter().map(|&index| lines[index]));
+    }
+    result
+}
+
+#[derive(Debug, Clone)]
+enum LineType {
+    Import(Import),
+    Comment(String),
+    Empty,
+    Other(String),
+}
+
+fn classify_line(line: &str) -> LineType {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        LineType::Empty
+    } else if let Some(import) = Import::parse(trimmed) {
+        LineType::Import(import)
+    } else if is_comment(trimmed) && !is_directive(trimmed) {
+        LineType::Comment(trimmed.to_string())
+    } else {
+        LineType::Other(trimmed.to_string())
+    }
+}
+
+/// Returns the index of the configured group an import path belongs to. Paths matching no
+/// pattern belong to the group after the last configured one.
+fn group_index(path: &str, groups: &[String]) -> usize {
+    groups
+        .iter()
+        .position(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => path.starts_with(prefix),
+            None => path == pattern,
+        })
+        .unwrap_or(groups.len())
+}
+
+/// Sorts a run of imports by module path and splits it into the configured groups, separated by
+/// exactly one empty line.
+fn sort_imports(imports: &mut Vec<Import>, config: &ImportConfig) -> Vec<String> {
+    imports.sort_by(|a, b| {
+        group_index(&a.path, &config.groups)
+            .cmp(&group_index(&b.path, &config.groups))
+            .then_with(|| a.path.cmp(&b.path))
+            .then_with(|| a.line.cmp(&b.line))
+    });
+
+    let mut result = Vec::new();
+    let mut previous_group = None;
+    for import in imports.drain(..) {
+        let group = group_index(&import.path, &config.groups);
+        if previous_group.is_some_and(|previous| previous != group) {
+            result.push(String::new());
+        }
+        previous_group = Some(group);
+        result.push(import.line);
+    }
+    result
+}
+
+/// Sorts the imports at the start of the module. Returns the formatted import section and the
+/// index of the first line that is not part of it.
+pub(crate) fn process_import_section(
+    lines: &[&str],
+    config: &ImportConfig,
+) -> (Vec<String>, usize) {
+    let mut result = Vec::new();
+    let mut current_import_group = Vec::new();
+    let mut end_index = 0;
+
+    for (i, line) in lines.iter().enumerate() {
+        let line_type = classify_line(line);
+
+        match line_type {
+            LineType::Import(import) => {
+                current_import_group.push(import);
+                end_index = i + 1;
+            }
+            LineType::Comment(comment) => {
+                // If we have imports in the current group, sort and add them
+                if !current_import_group.is_empty() {
+                    result.extend(sort_imports(&mut current_import_group, config));
+                    // Add empty line after imports before comment
+                    result.push(String::new());
+                }
+                // Add the comment
+                result.push(comment);
+                end_index = i + 1;
+            }
+            LineType::Empty => {
+                // Empty lines are preserved in their position, but avoid multiple consecutive empty
+                // lines. Empty lines within a run of imports are replaced by the group separators.
+                if current_import_group.is_empty()
+                    && !result.is_empty()
+                    && !result.last().is_some_and(|s: &String| s.is_empty())
+                {
+                    result.push(String::new());
+                    end_index = i + 1;
+                }
+            }
+            LineType::Other(content) => {
+                // Stop processing when we hit const or other non-import content
+                if ConstDecl::parse(&content).is_some() {
+                    break;
+                }
+                // If we have imports in the current group, sort and add them
+                if !current_import_group.is_empty() {
+                    result.extend(sort_imports(&mut current_import_group, config));
+                }
+                break;
+            }
+        }
+    }
+
+    // Handle any remaining imports in the current group
+    if !current_import_group.is_empty() {
+        result.extend(sort_imports(&mut current_import_group, config));
+    }
+
+    (result, end_index)
+}
@@ -0,0 +1,230 @@
+//! Rewrites of individual instructions within procedure bodies.
+
+use crate::{
+    config::{InstructionConfig, PushStyle},
+    directives::protected_lines,
+    is_comment, split_inline_comment,
+};
+
+/// Maximum number of immediate values a single `push` instruction accepts.
+const MAX_PUSH_IMMEDIATES: usize = 16;
+
+/// Instructions with more than one spelling, and the spelling they are rewritten to. Every pair
+/// is an exact alias: both forms assemble to the same operation.
+const INSTRUCTION_ALIASES: &[(&str, &str)] = &[
+    ("dup.0", "dup"),
+    ("dupw.0", "dupw"),
+    ("swap.1", "swap"),
+    ("swapw.1", "swapw"),
+];
+
+/// Returns the byte ranges of the whitespace-separated tokens of a piece of code. Whitespace
+/// inside string literals and bracketed lists, as in `push.[1, 2, 3, 4]`, does not separate
+/// tokens.
+fn token_spans(code: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut token_start = None;
+    let mut in_string = false;
+    let mut depth = 0usize;
+
+    for (i, c) in code.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if c.is_whitespace() && !in_string && depth == 0 {
+            if let Some(start) = token_start.take() {
+                spans.push((start, i));
+            }
+        } else if token_start.is_none() {
+            token_start = Some(i);
+        }
+    }
+    if let Some(start) = token_start {
+        spans.push((start, code.len()));
+    }
+
+    spans
+}
+
+/// Splits a piece of code into whitespace-separated tokens, keeping string literals whole.
+pub(crate) fn split_tokens(code: &str) -> Vec<&str> {
+    token_spans(code)
+        .into_iter()
+        .map(|(start, end)| &code[start..end])
+        .collect()
+}
+
+/// Calls `rewrite` on every token of a piece of code, replacing the token if it returns a value.
+/// Whitespace between tokens is kept as written.
+pub(crate) fn rewrite_tokens(
+    code: &str,
+    mut rewrite: impl FnMut(&str) -> Option<String>,
+) -> String {
+    let mut out = String::with_capacity(code.len());
+    let mut last_end = 0;
+    for (start, end) in token_spans(code) {
+        out.push_str(&code[last_end..start]);
+        let token = &code[start..end];
+        out.push_str(&rewrite(token).unwrap_or_else(|| token.to_string()));
+        last_end = end;
+    }
+    out.push_str(&code[last_end..]);
+    out
+}
+
+/// Returns the canonical spelling of an instruction, if it has one different from `token`.
+fn canonical_instruction(token: &str) -> Option<String> {
+    INSTRUCTION_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == token)
+        .map(|(_, canonical)| canonical.to_string())
+}
+
+/// Rewrites aliased instructions to their canonical spelling on every line that is not a comment
+/// or covered by a formatter directive.
+pub(crate) fn canonicalize_instructions(
+    lines: Vec<String>,
+    config: &InstructionConfig,
+) -> Vec<String> {
+    if !config.canonicalize {
+        return lines;
+    }
+
+    let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let protected = protected_lines(&line_refs);
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if protected[i] || is_comment(line) {
+                return line.clone();
+            }
+            let (code, comment) = split_inline_comment(line);
+            format!("{}{comment}", rewrite_tokens(code, canonical_instruction))
+        })
+        .collect()
+}
+
+/// Returns the immediates of a `push` whose values are all numeric literals of a single field
+/// element. Pushes of constants or words are never merged or split, since a constant may hold a
+/// whole word.
+fn push_values(token: &str) -> Option<Vec<&str>> {
+    let values: Vec<&str> = token.strip_prefix("push.")?.split('.').collect();
+    let is_felt = |value: &str| {
+        value.chars().all(|c| c.is_ascii_digit())
+            || value
+                .strip_prefix("0x")
+                .is_some_and(|hex| hex.len() <= 16 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+    };
+    values
+        .iter()
+        .all(|value| !value.is_empty() && is_felt(value))
+        .then_some(values)
+}
+
+fn render_push(values: &[&str]) -> String {
+    format!("push.{}", values.join("."))
+}
+
+/// Merges or splits the pushes within a single line of code. Returns `None` if nothing changes.
+fn restyle_pushes(code: &str, style: PushStyle) -> Option<String> {
+    let tokens = split_tokens(code);
+    let mut restyled: Vec<String> = Vec::with_capacity(tokens.len());
+    let mut pending: Vec<&str> = Vec::new();
+
+    for token in &tokens {
+        match (push_values(token), style) {
+            (Some(values), PushStyle::Merge) => {
+                if pending.len() + values.len() > MAX_PUSH_IMMEDIATES && !pending.is_empty() {
+                    restyled.push(render_push(&pending));
+                    pending.clear();
+                }
+                pending.extend(values);
+            }
+            (Some(values), _) => restyled.extend(values.iter().map(|value| render_push(&[value]))),
+            (None, _) => {
+                if !pending.is_empty() {
+                    restyled.push(render_push(&pending));
+                    pending.clear();
+                }
+                restyled.push(token.to_string());
+            }
+        }
+    }
+    if !pending.is_empty() {
+        restyled.push(render_push(&pending));
+    }
+
+    (restyled != tokens).then(|| restyled.join(" "))
+}
+
+/// Merges adjacent multi-value pushes into one, or splits them into one push per value, within
+/// lines and, when merging, across consecutive lines holding only pushes. A line with an inline
+/// comment is never merged into the line below it.
+pub(crate) fn restyle_push_instructions(
+    lines: Vec<String>,
+    config: &InstructionConfig,
+) -> Vec<String> {
+    if config.push == PushStyle::Preserve {
+        return lines;
+    }
+
+    let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let protected = protected_lines(&line_refs);
+    let mut result: Vec<String> = Vec::with_capacity(lines.len());
+    // Whether the last line in `result` holds only pushes and may absorb the next line.
+    let mut last_is_open_push_run = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        if protected[i] || is_comment(line) || line.trim().is_empty() {
+            result.push(line.clone());
+            last_is_open_push_run = false;
+            continue;
+        }
+
+        let (code, comment) = split_inline_comment(line);
+        let indent = &code[..code.len() - code.trim_start().len()];
+        let code = match restyle_pushes(code, config.push) {
+            Some(restyled) => format!("{indent}{restyled}"),
+            None => code.to_string(),
+        };
+        let only_pushes = split_tokens(&code)
+            .iter()
+            .all(|token| push_values(token).is_some());
+
+        if config.push == PushStyle::Merge
+            && last_is_open_push_run
+            && only_pushes
+            && let Some(previous) = result.last()
+            && previous.len() - previous.trim_start().len() == indent.len()
+        {
+            let merged = format!("{} {}", previous.trim(), code.trim());
+            if let Some(merged) = restyle_pushes(&merged, PushStyle::Merge)
+                && split_tokens(&merged).len() == 1
+            {
+                let merged_line = format!("{indent}{merged}{}", comment_suffix(comment));
+                *result.last_mut().unwrap() = merged_line;
+                last_is_open_push_run = comment.is_empty();
+                continue;
+            }
+        }
+
+        result.push(format!("{}{}", code.trim_end(), comment_suffix(comment)));
+        last_is_open_push_run = only_pushes && comment.is_empty();
+    }
+
+    result
+}
+
+/// Formats an inline comment to follow code, separated by a single space.
+fn comment_suffix(comment: &str) -> String {
+    if comment.is_empty() {
+        String::new()
+    } else {
+        format!(" {comment}")
+    }
+}
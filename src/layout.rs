@@ -0,0 +1,163 @@
+//! Placement of instructions on lines: one instruction per line, or runs of instructions packed
+//! onto as few lines as the width limit allows.
+
+use crate::{
+    ConstructType,
+    brackets::advice_map_continuations,
+    config::{FormatConfig, InstructionLayout},
+    directives::protected_lines,
+    instructions::split_tokens,
+    is_comment, is_decorator, is_single_export_line, leading_word, split_inline_comment,
+};
+
+/// Returns true if the line holds only instructions within a block: no block keywords, no
+/// attributes and no comment of its own.
+fn is_instruction_line(line: &str) -> bool {
+    let indent = line.len() - line.trim_start().len();
+    if indent == 0 || is_comment(line) || is_decorator(line) || line.trim().is_empty() {
+        return false;
+    }
+    let (code, _) = split_inline_comment(line);
+    split_tokens(code).iter().all(|token| {
+        leading_word(token)
+            .and_then(ConstructType::from_str)
+            .is_none()
+    })
+}
+
+/// Marks the lines whose instructions must stay where they are: lines protected by a directive,
+/// and the value lines of multi-line advice map entries.
+fn fixed_lines(lines: &[String]) -> Vec<bool> {
+    let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    protected_lines(&line_refs)
+        .into_iter()
+        .zip(advice_map_continuations(&line_refs))
+        .map(|(protected, continuation)| protected || continuation)
+        .collect()
+}
+
+/// Rearranges the instructions of every block according to the configured layout.
+pub(crate) fn layout_instructions(lines: Vec<String>, config: &FormatConfig) -> Vec<String> {
+    match config.instructions.layout {
+        InstructionLayout::Preserve => lines,
+        InstructionLayout::OnePerLine => one_per_line(lines),
+        InstructionLayout::Packed => packed(lines, config.max_width),
+    }
+}
+
+/// Splits every line holding several instructions into one line per instruction. An inline
+/// comment stays with the last instruction of its line.
+fn one_per_line(lines: Vec<String>) -> Vec<String> {
+    let fixed = fixed_lines(&lines);
+    let mut result = Vec::with_capacity(lines.len());
+
+    for (i, line) in lines.iter().enumerate() {
+        if fixed[i] || !is_instruction_line(line) {
+            result.push(line.clone());
+            continue;
+        }
+        let (code, comment) = split_inline_comment(line);
+        let indent = &code[..code.len() - code.trim_start().len()];
+        let tokens = split_tokens(code);
+        for (j, token) in tokens.iter().enumerate() {
+            if j + 1 == tokens.len() && !comment.is_empty() {
+                result.push(format!("{indent}{token} {comment}"));
+            } else {
+                result.push(format!("{indent}{token}"));
+            }
+        }
+    }
+
+    result
+}
+
+/// Joins runs of consecutive instruction lines at the same indentation onto as few lines as fit
+/// within `max_width`. Runs are broken by comments, including stack comments, by empty lines, by
+/// block keywords and by lines with an inline comment, which are never joined.
+fn packed(lines: Vec<String>, max_width: usize) -> Vec<String> {
+    let fixed = fixed_lines(&lines);
+    let mut result: Vec<String> = Vec::with_capacity(lines.len());
+    let mut can_extend_last = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        let joinable =
+            !fixed[i] && is_instruction_line(line) && split_inline_comment(line).1.is_empty();
+        if !joinable {
+            result.push(line.clone());
+            can_extend_last = false;
+            continue;
+        }
+
+        let indent = &line[..line.len() - line.trim_start().len()];
+        if can_extend_last && let Some(previous) = result.last_mut() {
+            let same_indent = previous.len() - previous.trim_start().len() == indent.len();
+            let joined_width = previous.chars().count() + 1 + line.trim().chars().count();
+            if same_indent && joined_width <= max_width {
+                previous.push(' ');
+                previous.push_str(line.trim());
+                continue;
+            }
+        }
+        result.push(line.clone());
+        can_extend_last = true;
+    }
+
+    result
+}
+
+/// Splits lines holding block keywords next to other code so that every block keyword is on a
+/// line of its own. Instructions between keywords stay together on one line, and an inline
+/// comment stays with the last line. Indentation is left to the formatter.
+pub(crate) fn expand_inline_blocks(lines: &[&str]) -> Vec<String> {
+    let protected = protected_lines(lines);
+    let mut result = Vec::with_capacity(lines.len());
+
+    for (i, line) in lines.iter().enumerate() {
+        let (code, comment) = split_inline_comment(line);
+        let code = code.trim();
+        let is_keyword = |token: &str| {
+            leading_word(token)
+                .and_then(ConstructType::from_str)
+                .is_some()
+        };
+        let tokens = split_tokens(code);
+        if protected[i]
+            || is_comment(line)
+            || is_decorator(line)
+            || is_single_export_line(code)
+            || code.starts_with("proc ")
+            || code.starts_with("pub proc ")
+            || tokens.len() < 2
+            || !tokens.iter().any(|token| is_keyword(token))
+        {
+            result.push(line.to_string());
+            continue;
+        }
+
+        let mut parts: Vec<String> = Vec::new();
+        let mut run: Vec<&str> = Vec::new();
+        for token in tokens {
+            if is_keyword(token) {
+                if !run.is_empty() {
+                    parts.push(run.join(" "));
+                    run.clear();
+                }
+                parts.push(token.to_string());
+            } else {
+                run.push(token);
+            }
+        }
+        if !run.is_empty() {
+            parts.push(run.join(" "));
+        }
+        if let Some(last) = parts.last_mut()
+            && !comment.is_empty()
+        {
+            last.push(' ');
+            last.push_str(comment);
+        }
+        result.extend(parts);
+    }
+
+    result
+}
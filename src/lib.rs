@@ -1,17 +1,57 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     io::{self, BufRead, BufReader, Write},
+    ops::Range,
     path::Path,
 };
 
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+mod attributes;
+mod blank_lines;
+mod brackets;
+mod config;
+mod const_eval;
+mod constants;
+mod diagnostics;
+mod directives;
+mod imports;
+mod instructions;
+mod layout;
+mod literals;
+mod migrate;
+mod recovery;
+mod reexports;
+mod reorder;
+mod signatures;
+
+use attributes::{format_attributes, rewrite_locals};
+use blank_lines::apply_blank_line_policy;
+use brackets::{advice_map_continuations, format_bracketed_lists, join_advice_maps};
+pub use config::{
+    BlankLineConfig, CONFIG_FILE_NAME, ConstantConfig, DEFAULT_MAX_WIDTH, FormatConfig, HexCase,
+    ImportConfig, InlineBlocks, InstructionConfig, InstructionLayout, LiteralConfig, LocalsStyle,
+    ModuleConfig, ProcedureConfig, ProcedureOrder, PushStyle, ReexportConfig, Syntax,
+};
+pub use const_eval::ModuleResolver;
+use constants::{ConstDecl, format_constants};
+pub use diagnostics::{Diagnostic, DiagnosticFormat, Severity};
+use directives::protected_lines;
+use imports::{analyze_imports, hoist_stray_imports, process_import_section};
+use instructions::{canonicalize_instructions, restyle_push_instructions, split_tokens};
+use layout::{expand_inline_blocks, layout_instructions};
+use literals::normalize_literals;
+pub use migrate::Migration;
+use reexports::{normalize_reexports, sort_reexports};
+use reorder::reorder_procedures;
+use signatures::{format_signatures, join_signatures};
+
 static SINGLE_LINE_EXPORT_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^export\..*(?:(?:::)|(?:->)).*$").unwrap());
 
 #[derive(Debug, PartialEq, Clone)]
-enum ConstructType {
+pub(crate) enum ConstructType {
     Proc,
     Export,
     Begin,
@@ -23,7 +63,7 @@ enum ConstructType {
 }
 
 impl ConstructType {
-    fn from_str(s: &str) -> Option<Self> {
+    pub(crate) fn from_str(s: &str) -> Option<Self> {
         match s {
             "proc" => Some(Self::Proc),
             "export" => Some(Self::Export),
@@ -40,7 +80,7 @@ impl ConstructType {
 
 const INDENT: &str = "    ";
 
-fn is_comment(line: &str) -> bool {
+pub(crate) fn is_comment(line: &str) -> bool {
     line.trim_start().starts_with('#')
 }
 
@@ -49,20 +89,16 @@ fn is_stack_comment(line: &str) -> bool {
     trimmed.starts_with("# => [") || trimmed.starts_with("#! => [")
 }
 
-fn is_single_export_line(line: &str) -> bool {
+pub(crate) fn is_single_export_line(line: &str) -> bool {
     SINGLE_LINE_EXPORT_REGEX.is_match(line)
 }
 
-fn is_use_statement(line: &str) -> bool {
-    line.trim_start().starts_with("use.")
-}
-
-fn is_decorator(line: &str) -> bool {
+pub(crate) fn is_decorator(line: &str) -> bool {
     let trimmed = line.trim();
     trimmed.starts_with('@') && !is_comment(trimmed)
 }
 
-fn is_proc_or_export(line: &str) -> bool {
+pub(crate) fn is_proc_or_export(line: &str) -> bool {
     let trimmed = line.trim();
     trimmed.starts_with("proc.")
         || trimmed.starts_with("export.")
@@ -70,93 +106,254 @@ fn is_proc_or_export(line: &str) -> bool {
         || trimmed.starts_with("pub proc ")
 }
 
-fn is_section_separator_comment(line: &str) -> bool {
-    let trimmed = line.trim_start();
-    (trimmed.starts_with("# ====") || trimmed.starts_with("#! ====")) && trimmed.contains("====")
+/// Splits a line into its code and its inline comment, if any. The comment part starts at the
+/// first `#` outside of a string literal, and the code part keeps its surrounding whitespace.
+pub(crate) fn split_inline_comment(line: &str) -> (&str, &str) {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return line.split_at(i),
+            _ => {}
+        }
+    }
+    (line, "")
 }
 
-#[derive(Debug, Clone)]
-enum LineType {
-    Import(String),
-    Comment(String),
-    Empty,
-    Other(String),
+/// Returns the code portion of a line, without any inline comment.
+pub(crate) fn strip_inline_comment(line: &str) -> &str {
+    split_inline_comment(line).0.trim()
 }
 
-fn classify_line(line: &str) -> LineType {
-    let trimmed = line.trim();
-    if trimmed.is_empty() {
-        LineType::Empty
-    } else if is_use_statement(trimmed) {
-        LineType::Import(trimmed.to_string())
-    } else if is_comment(trimmed) {
-        LineType::Comment(trimmed.to_string())
+/// Returns the leading keyword of a line of code. Both `proc name` and `pub proc name` yield
+/// `proc`.
+pub(crate) fn leading_word(code: &str) -> Option<&str> {
+    if code.starts_with("pub proc ") || code.starts_with("proc ") {
+        Some("proc")
     } else {
-        LineType::Other(trimmed.to_string())
+        code.split('.').next()
     }
 }
 
-fn process_import_section(lines: &[&str]) -> (Vec<String>, usize) {
-    let mut result = Vec::new();
-    let mut current_import_group = Vec::new();
-    let mut end_index = 0;
+/// Returns the block constructs a line opens, continues or closes, in order. Besides the leading
+/// keyword, a line may hold further block keywords, as in `if.true push.1 else push.0 end`.
+pub(crate) fn line_constructs(line: &str) -> Vec<ConstructType> {
+    let trimmed = line.trim();
+    if trimmed.is_empty()
+        || is_comment(trimmed)
+        || is_decorator(trimmed)
+        || is_single_export_line(trimmed)
+    {
+        return Vec::new();
+    }
+    let code = strip_inline_comment(trimmed);
+    let mut constructs: Vec<ConstructType> = leading_word(code)
+        .and_then(ConstructType::from_str)
+        .into_iter()
+        .collect();
+    constructs.extend(trailing_constructs(code));
+    constructs
+}
 
-    for (i, line) in lines.iter().enumerate() {
-        let line_type = classify_line(line);
+/// Returns the block constructs of the tokens following the first one on a line of code. The
+/// signature of a procedure declared in the modern syntax holds no block keywords.
+fn trailing_constructs(code: &str) -> Vec<ConstructType> {
+    if code.starts_with("proc ") || code.starts_with("pub proc ") {
+        return Vec::new();
+    }
+    split_tokens(code)
+        .iter()
+        .skip(1)
+        .filter_map(|token| leading_word(token).and_then(ConstructType::from_str))
+        .collect()
+}
 
-        match line_type {
-            LineType::Import(import) => {
-                current_import_group.push(import);
-                end_index = i + 1;
-            }
-            LineType::Comment(comment) => {
-                // If we have imports in the current group, sort and add them
-                if !current_import_group.is_empty() {
-                    current_import_group.sort();
-                    result.extend(current_import_group.drain(..));
-                    // Add empty line after imports before comment
-                    result.push(String::new());
-                }
-                // Add the comment
-                result.push(comment);
-                end_index = i + 1;
-            }
-            LineType::Empty => {
-                // Empty lines are preserved in their position, but avoid multiple consecutive empty lines
-                if !result.is_empty() && !result.last().map_or(false, |s| s.is_empty()) {
-                    result.push(String::new());
-                    end_index = i + 1;
-                }
-            }
-            LineType::Other(content) => {
-                // Stop processing when we hit const or other non-import content
-                if content.starts_with("const.") {
-                    break;
-                }
-                // If we have imports in the current group, sort and add them
-                if !current_import_group.is_empty() {
-                    current_import_group.sort();
-                    result.extend(current_import_group.drain(..));
-                }
-                break;
+/// Applies the net effect of a block construct to the construct stack and indentation level.
+fn track_construct(
+    construct: ConstructType,
+    construct_stack: &mut Vec<ConstructType>,
+    indentation_level: &mut usize,
+) {
+    match construct {
+        ConstructType::End => {
+            if let Some(last_construct) = construct_stack.pop()
+                && last_construct != ConstructType::End
+                && *indentation_level > 0
+            {
+                *indentation_level -= 1;
             }
         }
+        ConstructType::Else => {}
+        construct => {
+            construct_stack.push(construct);
+            *indentation_level += 1;
+        }
     }
+}
 
-    // Handle any remaining imports in the current group
-    if !current_import_group.is_empty() {
-        current_import_group.sort();
-        result.extend(current_import_group);
-    }
-
-    (result, end_index)
+pub(crate) fn is_section_separator_comment(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    (trimmed.starts_with("# ====") || trimmed.starts_with("#! ====")) && trimmed.contains("====")
 }
 
+/// Formats Miden Assembly source code using the default configuration.
 pub fn format_code(code: &str) -> String {
+    format_code_with_config(code, &FormatConfig::default())
+}
+
+/// Formats Miden Assembly source code using the given configuration.
+///
+/// Top-level items whose blocks are not balanced, such as a procedure missing its `end`, are
+/// left as written; see [`format_code_recovering`].
+pub fn format_code_with_config(code: &str, config: &FormatConfig) -> String {
+    format_code_recovering(code, config).code
+}
+
+/// Formatted source code, together with findings about the parts that could not be formatted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formatted {
+    pub code: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Formats Miden Assembly source code that may be broken, as it usually is while being edited.
+///
+/// Top-level items whose blocks are not balanced are left exactly as written and reported, and
+/// everything around them is formatted. If a syntax dialect is configured, declarations that
+/// cannot be rewritten into it are reported as well. While a module has broken items, imports are
+/// neither removed nor hoisted and procedures and re-exports are not reordered, since those need
+/// the whole module.
+pub fn format_code_recovering(code: &str, config: &FormatConfig) -> Formatted {
     let lines: Vec<&str> = code.lines().collect();
+    let broken = recovery::broken_regions(&lines);
+    if broken.is_empty() {
+        return format_module(code, config);
+    }
+
+    let mut config = config.clone();
+    config.imports.remove_unused = false;
+    config.imports.hoist = false;
+    config.reexports.sort = false;
+    config.procedures.order = ProcedureOrder::Preserve;
+    config.procedures.alphabetical = false;
+
+    // Every piece of output, with the source lines it was made from, not counting the empty lines
+    // around them.
+    let mut pieces: Vec<(Range<usize>, Vec<String>)> = Vec::new();
+    let mut diagnostics = Vec::new();
+    let mut format_segment = |segment: Range<usize>| {
+        let segment = trim_blank_lines(&lines, segment);
+        if segment.is_empty() {
+            return None;
+        }
+        let formatted = format_module(&lines[segment.clone()].join("\n"), &config);
+        diagnostics.extend(formatted.diagnostics.into_iter().map(|mut diagnostic| {
+            diagnostic.line += segment.start;
+            diagnostic
+        }));
+        let code = formatted.code.lines().map(str::to_string).collect();
+        Some((segment, code))
+    };
+    let mut start = 0;
+    for region in &broken {
+        pieces.extend(format_segment(start..region.lines.start));
+        let region_lines = trim_blank_lines(&lines, region.lines.clone());
+        let verbatim = lines[region_lines.clone()]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        pieces.push((region_lines, verbatim));
+        start = region.lines.end;
+    }
+    pieces.extend(format_segment(start..lines.len()));
+
+    // Keep the empty lines written between the pieces, collapsed to one as elsewhere, unless the
+    // blank-line policy asks for another number.
+    let mut output: Vec<String> = Vec::new();
+    let mut previous_end: Option<usize> = None;
+    for (source, piece) in pieces {
+        if let Some(previous_end) = previous_end {
+            let written = (source.start - previous_end).min(1);
+            let mut joined: Vec<&str> = output.iter().map(String::as_str).collect();
+            let last = joined.len() - 1;
+            joined.extend(piece.iter().map(String::as_str));
+            let count = blank_lines::policy_gap(&joined, last, last + 1, &config.blank_lines)
+                .unwrap_or(written);
+            output.extend(std::iter::repeat_n(String::new(), count));
+        }
+        output.extend(piece);
+        previous_end = Some(source.end);
+    }
+
+    let mut code = output.join("\n");
+    code.push('\n');
+    diagnostics.extend(broken.into_iter().filter_map(|region| region.diagnostic));
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    Formatted { code, diagnostics }
+}
+
+/// Narrows a range of lines to exclude the empty lines at its start and end.
+fn trim_blank_lines(lines: &[&str], range: Range<usize>) -> Range<usize> {
+    let is_code = |&i: &usize| !lines[i].trim().is_empty();
+    let start = range.clone().find(is_code).unwrap_or(range.end);
+    let end = range.rev().find(is_code).map_or(start, |end| end + 1);
+    start..end
+}
+
+/// Formats a module whose block structure is balanced. The findings are those of rewriting the
+/// module into the configured syntax dialect, with line numbers relative to `code`.
+fn format_module(code: &str, config: &FormatConfig) -> Formatted {
+    let mut diagnostics = Vec::new();
+    let migrated;
+    let code = match config.syntax {
+        Some(syntax) => {
+            let migration = migrate::migrate(code, syntax);
+            diagnostics = migration.diagnostics;
+            migrated = migration.code;
+            &migrated
+        }
+        None => code,
+    };
+    let mut lines: Vec<&str> = code.lines().collect();
+
+    if config.imports.remove_unused {
+        let protected = protected_lines(&lines);
+        let removable = analyze_imports(&lines).removable;
+        let mut index = 0;
+        lines.retain(|_| {
+            let keep = protected[index] || removable.binary_search(&index).is_err();
+            index += 1;
+            keep
+        });
+    }
+
+    if config.imports.hoist {
+        lines = hoist_stray_imports(&lines);
+    }
+
+    let sorted_lines = sort_reexports(&lines, &config.reexports);
+    lines = sorted_lines.iter().map(String::as_str).collect();
+
+    let joined_lines = join_signatures(&lines);
+    lines = joined_lines.iter().map(String::as_str).collect();
+
+    let joined_advice_maps = join_advice_maps(&lines);
+    lines = joined_advice_maps.iter().map(String::as_str).collect();
+
+    let reordered_lines = reorder_procedures(&lines, &config.procedures);
+    lines = reordered_lines.iter().map(String::as_str).collect();
+
+    let expanded_lines;
+    if config.instructions.inline_blocks == InlineBlocks::Expand {
+        expanded_lines = expand_inline_blocks(&lines);
+        lines = expanded_lines.iter().map(String::as_str).collect();
+    }
 
     // Extract and sort imports
-    let (sorted_imports, import_end_index) = process_import_section(&lines);
+    let (sorted_imports, import_end_index) = process_import_section(&lines, &config.imports);
 
     let mut formatted_code = String::new();
     let mut indentation_level = 0;
@@ -182,10 +379,31 @@ pub fn format_code(code: &str) -> String {
 
     // Process remaining lines (skip the import section)
     let remaining_lines = &lines[import_end_index..];
+    let protected: Vec<bool> = protected_lines(remaining_lines)
+        .into_iter()
+        .zip(advice_map_continuations(remaining_lines))
+        .map(|(protected, continuation)| protected || continuation)
+        .collect();
 
     for (i, line) in remaining_lines.iter().enumerate() {
         let trimmed_line = line.trim();
 
+        if protected[i] {
+            // Lines covered by a formatter directive, and the values of advice map entries left on
+            // several lines, are emitted exactly as written, but the block structure is still
+            // tracked so that indentation after the region is correct.
+            for construct in line_constructs(line) {
+                track_construct(construct, &mut construct_stack, &mut indentation_level);
+            }
+
+            formatted_code.push_str(line);
+            formatted_code.push('\n');
+            last_line_was_empty = trimmed_line.is_empty();
+            last_was_export_line = false;
+            last_line_was_stack_comment = false;
+            continue;
+        }
+
         if !trimmed_line.is_empty() {
             if is_decorator(trimmed_line) {
                 // Handle decorators - they should be formatted at the current indentation level
@@ -231,16 +449,14 @@ pub fn format_code(code: &str) -> String {
             last_was_export_line = false;
 
             // Remove inline comment for keyword extraction.
-            let code_without_comment = trimmed_line.split('#').next().unwrap().trim();
+            let code_without_comment = strip_inline_comment(trimmed_line);
 
             // Handle new procedure syntax (proc name or pub proc name)
-            let first_word = if code_without_comment.starts_with("pub proc ") {
-                Some("proc")
-            } else if code_without_comment.starts_with("proc ") {
-                Some("proc")
-            } else {
-                code_without_comment.split('.').next()
-            };
+            let first_word = leading_word(code_without_comment);
+
+            // Block keywords after the first word, as in `if.true push.1 else push.0 end`. The line
+            // is indented according to its first word, and these are tracked once it is emitted.
+            let trailing = trailing_constructs(code_without_comment);
 
             // Special handling for stack comment newline
             if last_line_was_stack_comment {
@@ -259,7 +475,7 @@ pub fn format_code(code: &str) -> String {
             {
                 match construct {
                     ConstructType::End => {
-                        let was_proc_or_export_end =
+                        let mut was_proc_or_export_end =
                             if let Some(last_construct) = construct_stack.pop() {
                                 let is_proc_or_export = matches!(
                                     last_construct,
@@ -278,6 +494,19 @@ pub fn format_code(code: &str) -> String {
                         formatted_code.push('\n');
                         last_line_was_empty = false;
 
+                        for construct in trailing {
+                            was_proc_or_export_end = construct == ConstructType::End
+                                && matches!(
+                                    construct_stack.last(),
+                                    Some(ConstructType::Proc | ConstructType::Export)
+                                );
+                            track_construct(
+                                construct,
+                                &mut construct_stack,
+                                &mut indentation_level,
+                            );
+                        }
+
                         // Add blank line after procedure/export end if there's more content
                         if was_proc_or_export_end && i + 1 < remaining_lines.len() {
                             let next_line = remaining_lines[i + 1].trim();
@@ -320,6 +549,10 @@ pub fn format_code(code: &str) -> String {
                     _ => {}
                 }
 
+                for construct in trailing {
+                    track_construct(construct, &mut construct_stack, &mut indentation_level);
+                }
+
                 continue;
             }
 
@@ -327,6 +560,10 @@ pub fn format_code(code: &str) -> String {
             formatted_code.push_str(trimmed_line);
             formatted_code.push('\n');
             last_line_was_empty = false;
+
+            for construct in trailing {
+                track_construct(construct, &mut construct_stack, &mut indentation_level);
+            }
         } else {
             // This is an empty line in the input
             // Check if we should skip adding it (e.g., between comment and const)
@@ -336,7 +573,7 @@ pub fn format_code(code: &str) -> String {
                 let prev_line = prev_lines.last().map(|l| l.trim()).unwrap_or("");
 
                 // Skip empty line if previous line is a comment and next line is a const
-                is_comment(prev_line) && next_line.starts_with("const.")
+                is_comment(prev_line) && ConstDecl::parse(next_line).is_some()
             } else {
                 false
             };
@@ -357,12 +594,20 @@ pub fn format_code(code: &str) -> String {
     // Final pass: collapse any remaining multiple consecutive empty lines (3+ becomes 1)
     // Also prevent blank lines between comments and proc/export declarations
     let lines: Vec<&str> = formatted_code.lines().collect();
+    let protected = protected_lines(&lines);
     let mut final_output = String::new();
     let mut consecutive_empty_count = 0;
 
     for (i, line) in lines.iter().enumerate() {
         let is_empty = line.trim().is_empty();
 
+        if protected[i] {
+            final_output.push_str(line);
+            final_output.push('\n');
+            consecutive_empty_count = 0;
+            continue;
+        }
+
         if is_empty {
             consecutive_empty_count += 1;
 
@@ -371,8 +616,13 @@ pub fn format_code(code: &str) -> String {
                 let prev_line = lines[i - 1].trim();
                 let next_line = lines[i + 1].trim();
                 // Skip empty lines between regular comments and proc/export/const, but preserve them after section separators
+                // When local counts are rewritten, an `@locals` attribute may move between a
+                // procedure and its comment, so attributes are treated like the procedure.
+                let rewrites_locals = config.procedures.locals != LocalsStyle::Preserve;
                 is_comment(prev_line)
-                    && (is_proc_or_export(next_line) || next_line.starts_with("const."))
+                    && (is_proc_or_export(next_line)
+                        || (rewrites_locals && is_decorator(next_line))
+                        || ConstDecl::parse(next_line).is_some())
                     && !is_section_separator_comment(prev_line)
             } else {
                 false
@@ -391,16 +641,109 @@ pub fn format_code(code: &str) -> String {
         }
     }
 
+    let lines: Vec<String> = final_output.lines().map(str::to_string).collect();
+    let lines = normalize_reexports(lines);
+    let lines = rewrite_locals(lines, config.procedures.locals);
+    let lines = format_attributes(lines, &config.procedures);
+    let lines = canonicalize_instructions(lines, &config.instructions);
+    let lines = restyle_push_instructions(lines, &config.instructions);
+    let lines = layout_instructions(lines, config);
+    let lines = normalize_literals(lines, &config.literals);
+    let lines = format_bracketed_lists(lines, config.max_width);
+    let lines = format_constants(lines, &config.constants);
+    let lines = apply_blank_line_policy(lines, &config.blank_lines);
+    final_output = format_signatures(lines, config.max_width).join("\n");
+
     // Ensure the final output ends with exactly one newline
     while final_output.ends_with('\n') {
         final_output.pop();
     }
     final_output.push('\n');
 
-    final_output
+    Formatted {
+        code: final_output,
+        diagnostics,
+    }
+}
+
+/// Reports imports that are never referenced, imports that repeat a path or an alias, and imports
+/// placed after the import block.
+pub fn check_imports(code: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = code.lines().collect();
+    analyze_imports(&lines).diagnostics
+}
+
+/// Evaluates the constant declarations of a module, reporting undefined names, cyclic
+/// definitions, values outside the field and constants too large for the `u32` immediates they
+/// are used as. Constants of imported modules are treated as unknown.
+pub fn check_constants(code: &str) -> Vec<Diagnostic> {
+    const_eval::check_constants(code, &|_| None)
+}
+
+/// Like [`check_constants`], reading imported modules through `resolver` to evaluate qualified
+/// constant names such as `u64::MAX`.
+pub fn check_constants_with_resolver(code: &str, resolver: &ModuleResolver) -> Vec<Diagnostic> {
+    const_eval::check_constants(code, resolver)
+}
+
+/// Rewrites the declarations of a module into the given syntax dialect: imports, constants,
+/// procedure declarations with their local counts, and re-exports. Anything that cannot be
+/// migrated is left as written and reported.
+pub fn migrate_code(code: &str, to: Syntax) -> Migration {
+    migrate::migrate(code, to)
 }
 
+/// Reports attributes separated from the procedure they decorate by empty lines or comments, and
+/// attributes that decorate no procedure. Attributes covered by a formatter directive are not
+/// checked.
+pub fn check_attributes(code: &str) -> Vec<Diagnostic> {
+    attributes::check_attributes(code)
+}
+
+/// Reports procedure and re-export declarations that are not written in the given syntax dialect
+/// or, if none is given, those whose dialect differs from that of the first one in the module.
+/// Imports and constants are not checked, and neither are lines covered by a formatter directive.
+pub fn check_syntax(code: &str, syntax: Option<Syntax>) -> Vec<Diagnostic> {
+    migrate::check_syntax(code, syntax)
+}
+
+/// Migrates a file in place to the given syntax dialect and formats the result. Returns the
+/// findings for everything that could not be migrated. The file is written even if there are
+/// findings: the lines they point at are left as written and everything else is migrated.
+pub fn migrate_file_with_config(
+    file_path: &Path,
+    to: Syntax,
+    config: &FormatConfig,
+) -> io::Result<Vec<Diagnostic>> {
+    let source = fs::read_to_string(file_path)?;
+    let migration = migrate_code(&source, to);
+
+    let mut config = config.clone();
+    config.syntax = Some(to);
+    config.constants.syntax = Some(to);
+    let formatted_code = format_code_with_config(&migration.code, &config);
+    fs::write(file_path, formatted_code)?;
+
+    Ok(migration.diagnostics)
+}
+
+/// Formats a file in place using the default configuration.
 pub fn format_file(file_path: &Path) -> io::Result<()> {
+    format_file_with_config(file_path, &FormatConfig::default())
+}
+
+/// Formats a file in place using the given configuration.
+pub fn format_file_with_config(file_path: &Path, config: &FormatConfig) -> io::Result<()> {
+    format_file_recovering(file_path, config)?;
+    Ok(())
+}
+
+/// Formats a file in place using the given configuration, returning the findings about the parts
+/// that were left unformatted; see [`format_code_recovering`].
+pub fn format_file_recovering(
+    file_path: &Path,
+    config: &FormatConfig,
+) -> io::Result<Vec<Diagnostic>> {
     let file = File::open(file_path)?;
     let mut input_code = String::new();
 
@@ -410,10 +753,10 @@ pub fn format_file(file_path: &Path) -> io::Result<()> {
         input_code.push('\n');
     }
 
-    let formatted_code = format_code(&input_code);
+    let formatted = format_code_recovering(&input_code, config);
 
     let mut file = File::create(file_path)?;
-    file.write_all(formatted_code.as_bytes())?;
+    file.write_all(formatted.code.as_bytes())?;
 
-    Ok(())
+    Ok(formatted.diagnostics)
 }
@@ -0,0 +1,125 @@
+//! Normalization of numeric literals in instruction immediates and constant values.
+
+use crate::{
+    config::{HexCase, LiteralConfig},
+    const_eval::parse_number,
+    directives::protected_lines,
+    is_comment, split_inline_comment,
+};
+
+/// Number of hex digits of a single field element. Longer hex literals denote words, so padding
+/// never crosses this length.
+const FELT_HEX_DIGITS: usize = 16;
+
+/// Rewrites a single numeric literal according to the configuration. Returns the literal
+/// unchanged if the rewrite would alter its value.
+fn normalize_literal(literal: &str, config: &LiteralConfig) -> String {
+    let normalized = if let Some(digits) = literal
+        .strip_prefix("0x")
+        .or_else(|| literal.strip_prefix("0X"))
+    {
+        let mut digits = match config.hex_case {
+            HexCase::Preserve => digits.to_string(),
+            HexCase::Lower => digits.to_ascii_lowercase(),
+            HexCase::Upper => digits.to_ascii_uppercase(),
+        };
+        if config.hex_pad > 0 && digits.len() <= FELT_HEX_DIGITS {
+            let padded_len = digits.len().div_ceil(config.hex_pad) * config.hex_pad;
+            if padded_len <= FELT_HEX_DIGITS {
+                digits = format!("{digits:0>padded_len$}");
+            }
+        }
+        let prefix = if config.normalize {
+            "0x"
+        } else {
+            &literal[..2]
+        };
+        format!("{prefix}{digits}")
+    } else if config.normalize && literal.len() > 1 {
+        let trimmed = literal.trim_start_matches('0');
+        if trimmed.is_empty() {
+            "0".to_string()
+        } else {
+            trimmed.to_string()
+        }
+    } else {
+        literal.to_string()
+    };
+
+    // A hex literal's length decides whether it denotes a felt or a word, so the length class
+    // must be kept as well as the value.
+    let is_hex = literal.len() > 2 && literal[1..].starts_with(['x', 'X']);
+    let same_kind = !is_hex
+        || (literal.len() - 2 <= FELT_HEX_DIGITS) == (normalized.len() - 2 <= FELT_HEX_DIGITS);
+    if same_kind && parse_number(&normalized) == parse_number(literal) {
+        normalized
+    } else {
+        literal.to_string()
+    }
+}
+
+/// Normalizes every numeric literal in a piece of code, leaving string literals untouched.
+fn normalize_code(code: &str, config: &LiteralConfig) -> String {
+    let chars: Vec<char> = code.chars().collect();
+    let mut out = String::with_capacity(code.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '"' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|&c| c == '"')
+                .map_or(chars.len(), |offset| i + 1 + offset + 1);
+            out.extend(&chars[i..end]);
+            i = end;
+        } else if c.is_ascii_digit()
+            && (i == 0 || !(chars[i - 1].is_ascii_alphanumeric() || chars[i - 1] == '_'))
+        {
+            let end = chars[i..]
+                .iter()
+                .position(|&c| !(c.is_ascii_alphanumeric() || c == '_'))
+                .map_or(chars.len(), |offset| i + offset);
+            let literal: String = chars[i..end].iter().collect();
+            let is_number = literal.chars().all(|c| c.is_ascii_digit())
+                || (literal.len() > 2
+                    && literal[1..].starts_with(['x', 'X'])
+                    && literal.starts_with('0')
+                    && literal[2..].chars().all(|c| c.is_ascii_hexdigit()));
+            if is_number {
+                out.push_str(&normalize_literal(&literal, config));
+            } else {
+                out.push_str(&literal);
+            }
+            i = end;
+        } else {
+            out.push(c);
+            i += 1;
+        }
+    }
+
+    out
+}
+
+/// Normalizes numeric literals on every line that is not a comment or covered by a formatter
+/// directive. Inline comments are kept as written.
+pub(crate) fn normalize_literals(lines: Vec<String>, config: &LiteralConfig) -> Vec<String> {
+    if !config.normalize && config.hex_case == HexCase::Preserve && config.hex_pad == 0 {
+        return lines;
+    }
+
+    let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let protected = protected_lines(&line_refs);
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if protected[i] || is_comment(line) {
+                return line.clone();
+            }
+            let (code, comment) = split_inline_comment(line);
+            format!("{}{comment}", normalize_code(code, config))
+        })
+        .collect()
+}
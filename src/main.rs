@@ -1,16 +1,28 @@
-use std::{fs, io, path::Path, process};
+use std::{
+    env, fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process,
+};
 
-use clap::Parser;
-use masm_formatter::{format_code, format_file};
+use clap::{Parser, Subcommand};
+use masm_formatter::{
+    Diagnostic, DiagnosticFormat, FormatConfig, Severity, Syntax, check_attributes,
+    check_constants_with_resolver, check_imports, check_syntax, format_code_recovering,
+    format_file_recovering, migrate_file_with_config,
+};
 
 #[derive(Parser)]
 #[command(
     bin_name = "masm-fmt",
     subcommand_required = false,
+    args_conflicts_with_subcommands = true,
     arg_required_else_help = true,
     version = "0.3.3"
 )]
 struct MasmFmtArgs {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// The folder or file path to search for .masm files.
     ///
     /// You can specify a folder to recursively format all .masm files,
@@ -19,75 +31,213 @@ struct MasmFmtArgs {
     /// Example:
     ///     masm-fmt source_dir
     ///     masm-fmt some_file.masm
-    path: String,
+    #[arg(required = true)]
+    path: Option<String>,
     /// Check for formatting issues without writing changes.
     #[arg(long)]
     check: bool,
+    /// Path to a masm-fmt.toml configuration file.
+    ///
+    /// If not given, masm-fmt.toml is looked up in the current directory and its ancestors.
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// How findings are printed: `plain`, `colored` or `json` (one object per line).
+    ///
+    /// Defaults to `colored` when writing to a terminal and `NO_COLOR` is not set, and to `plain`
+    /// otherwise. With `json`, progress messages are written to stderr.
+    #[arg(long, global = true)]
+    message_format: Option<DiagnosticFormat>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Rewrite .masm files into another syntax dialect, then format them.
+    ///
+    /// Anything that cannot be migrated is left as written and reported.
+    ///
+    /// Example:
+    ///     masm-fmt migrate --to modern source_dir
+    Migrate {
+        /// The syntax dialect to migrate to: `modern` or `legacy`.
+        #[arg(long)]
+        to: Syntax,
+        /// The folder or .masm file to migrate.
+        path: String,
+    },
+}
+
+/// Loads the configuration given on the command line, or the nearest masm-fmt.toml if none was
+/// given. Falls back to the default configuration if no file is found.
+fn load_config(explicit: Option<&Path>) -> io::Result<FormatConfig> {
+    let path = match explicit {
+        Some(path) => Some(path.to_path_buf()),
+        None => FormatConfig::discover(&env::current_dir()?),
+    };
+    match path {
+        Some(path) => FormatConfig::from_file(&path),
+        None => Ok(FormatConfig::default()),
+    }
+}
+
+/// Prints findings and progress messages in the requested format.
+struct Reporter {
+    format: DiagnosticFormat,
+}
+
+impl Reporter {
+    fn new(format: Option<DiagnosticFormat>) -> Self {
+        let format = format.unwrap_or_else(|| {
+            if io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none() {
+                DiagnosticFormat::Colored
+            } else {
+                DiagnosticFormat::Plain
+            }
+        });
+        Self { format }
+    }
+
+    /// Prints a progress message, keeping stdout machine-readable in the JSON format.
+    fn note(&self, message: &str) {
+        if self.format == DiagnosticFormat::Json {
+            eprintln!("{message}");
+        } else {
+            println!("{message}");
+        }
+    }
+
+    /// Prints the diagnostics found in the file at `path` with the given contents. Returns true if
+    /// any of them is an error.
+    fn report(&self, path: &Path, source: &str, diagnostics: &[Diagnostic]) -> bool {
+        let path = path.display().to_string();
+        for diagnostic in diagnostics {
+            // The snippet formats end with a newline, leaving an empty line between findings.
+            println!("{}", diagnostic.render(&path, source, self.format));
+        }
+        diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+/// Checks a single file, printing any findings. Returns Ok(true) if the file is not formatted or
+/// has errors.
+fn check_file(path: &Path, config: &FormatConfig, reporter: &Reporter) -> io::Result<bool> {
+    let original = fs::read_to_string(path)?;
+    let resolver = |module: &str| config.modules.resolve(module);
+    let mut diagnostics = check_imports(&original);
+    diagnostics.extend(check_constants_with_resolver(&original, &resolver));
+    diagnostics.extend(check_attributes(&original));
+
+    let formatted = format_code_recovering(&original, config);
+    // Declarations that formatting could not rewrite into the configured dialect are already
+    // reported by the formatter.
+    diagnostics.extend(
+        check_syntax(&original, config.syntax)
+            .into_iter()
+            .filter(|diagnostic| {
+                !formatted
+                    .diagnostics
+                    .iter()
+                    .any(|reported| reported.line == diagnostic.line)
+            }),
+    );
+    diagnostics.extend(formatted.diagnostics);
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+
+    let mut failed = reporter.report(path, &original, &diagnostics);
+    if original != formatted.code {
+        reporter.note(&format!("File is not formatted correctly: {path:?}"));
+        failed = true;
+    }
+    Ok(failed)
 }
 
-/// Recursively traverse directories in a DFS manner and either format or check every .masm file.
-/// Returns Ok(true) if any file is unformatted (in check mode), otherwise Ok(false).
-fn process_path(path: &Path, check: bool) -> io::Result<bool> {
-    let mut unformatted_found = false;
+/// Migrates a single file in place, printing everything that could not be migrated. Returns
+/// Ok(true) if anything was left unmigrated.
+fn migrate_file(
+    path: &Path,
+    to: Syntax,
+    config: &FormatConfig,
+    reporter: &Reporter,
+) -> io::Result<bool> {
+    reporter.note(&format!("Migrating file: {path:?}"));
+    let original = fs::read_to_string(path)?;
+    let diagnostics = migrate_file_with_config(path, to, config)?;
+    reporter.report(path, &original, &diagnostics);
+    // Every finding, even a warning, marks a line that was left unmigrated.
+    Ok(!diagnostics.is_empty())
+}
+
+/// Recursively traverse directories in a DFS manner and call `action` on every .masm file.
+/// Returns Ok(true) if `action` reported a failure for any file, otherwise Ok(false).
+fn process_path(
+    path: &Path,
+    action: &mut impl FnMut(&Path) -> io::Result<bool>,
+) -> io::Result<bool> {
+    let mut failure_found = false;
     if path.is_dir() {
         // DFS: For each entry in this directory, process recursively.
         for entry in fs::read_dir(path)? {
             let entry = entry?;
-            if process_path(&entry.path(), check)? {
-                unformatted_found = true;
+            if process_path(&entry.path(), action)? {
+                failure_found = true;
             }
         }
     } else if path.extension().and_then(|s| s.to_str()) == Some("masm") {
-        if check {
-            let original = fs::read_to_string(path)?;
-            let formatted = format_code(&original);
-            if original != formatted {
-                println!("File is not formatted correctly: {path:?}");
-                unformatted_found = true;
-            }
-        } else {
-            println!("Formatting file: {path:?}");
-            format_file(path)?;
-        }
+        failure_found = action(path)?;
     }
-    Ok(unformatted_found)
+    Ok(failure_found)
 }
 
 fn main() -> io::Result<()> {
     let args = MasmFmtArgs::parse();
+    let config = load_config(args.config.as_deref())?;
+    let reporter = Reporter::new(args.message_format);
+
+    let (path, failure_message) = match &args.command {
+        Some(Command::Migrate { path, .. }) => (
+            path.as_str(),
+            "Migration incomplete: some lines could not be migrated.",
+        ),
+        None if args.check => (
+            args.path.as_deref().unwrap_or_default(),
+            "Formatting check failed: some files are not formatted correctly or have errors.",
+        ),
+        None => (
+            args.path.as_deref().unwrap_or_default(),
+            "Formatting incomplete: some files have errors and were left partly unformatted.",
+        ),
+    };
+    let mut action = |path: &Path| match &args.command {
+        Some(Command::Migrate { to, .. }) => migrate_file(path, *to, &config, &reporter),
+        None if args.check => check_file(path, &config, &reporter),
+        None => {
+            reporter.note(&format!("Formatting file: {path:?}"));
+            let original = fs::read_to_string(path)?;
+            let diagnostics = format_file_recovering(path, &config)?;
+            Ok(reporter.report(path, &original, &diagnostics))
+        }
+    };
 
-    let source_path = Path::new(&args.path);
+    let source_path = Path::new(path);
     if source_path.exists() {
-        let unformatted = if source_path.is_file() {
+        let failed = if source_path.is_file() {
             if source_path.extension().and_then(|s| s.to_str()) == Some("masm") {
-                if args.check {
-                    let original = fs::read_to_string(source_path)?;
-                    let formatted = format_code(&original);
-                    if original != formatted {
-                        println!("File is not formatted correctly: {source_path:?}");
-                        true
-                    } else {
-                        false
-                    }
-                } else {
-                    println!("Formatting file: {source_path:?}");
-                    format_file(source_path)?;
-                    false
-                }
+                action(source_path)?
             } else {
                 eprintln!("The specified file is not a .masm file: {source_path:?}");
                 false
             }
         } else {
-            process_path(source_path, args.check)?
+            process_path(source_path, &mut action)?
         };
 
-        if args.check && unformatted {
-            eprintln!("Formatting check failed: some files are not formatted correctly.");
+        if failed {
+            eprintln!("{failure_message}");
             process::exit(1);
         }
     } else {
-        eprintln!("The specified path does not exist: {}", args.path);
+        eprintln!("The specified path does not exist: {path}");
     }
 
     Ok(())
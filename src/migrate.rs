@@ -0,0 +1,305 @@
+//! Rewriting of whole modules from one syntax dialect to the other.
+//!
+//! | legacy               | modern                       |
+//! |----------------------|------------------------------|
+//! | `use.std::sys`       | `use std::sys`               |
+//! | `const.X=1`          | `const X = 1`                |
+//! | `proc.foo`           | `proc foo`                   |
+//! | `export.foo`         | `pub proc foo`               |
+//! | `proc.foo.2`         | `@locals(2)` above `proc foo`|
+//! | `export.std::sys::x` | `pub use std::sys::x`        |
+
+use crate::{
+    attributes::locals_attribute, config::Syntax, constants::ConstDecl, diagnostics::Diagnostic,
+    directives::protected_lines, imports::Import, is_comment, is_decorator, signatures::Signature,
+    split_inline_comment,
+};
+
+/// The result of migrating a module: the rewritten source and everything that could not be
+/// migrated, which is left as written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    pub code: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A procedure declaration in the legacy syntax: `proc.foo`, `export.foo.2`.
+struct LegacyProc<'a> {
+    public: bool,
+    name: &'a str,
+    locals: Option<&'a str>,
+}
+
+/// What a line declares, as far as migration is concerned.
+enum Item<'a> {
+    Import(Import),
+    Const(ConstDecl),
+    LegacyProc(LegacyProc<'a>),
+    /// `export.path::to::proc`, optionally with an alias.
+    LegacyReexport(&'a str),
+    /// `pub use path::to::proc`, optionally with an alias.
+    ModernReexport(&'a str),
+    Signature(Signature),
+}
+
+/// Parses a trimmed line into an item that may need migrating. Returns `Err` with a message for
+/// legacy declarations that have no modern form.
+fn parse_item(line: &str) -> Option<Result<Item<'_>, String>> {
+    if let Some(import) = Import::parse(line) {
+        return Some(Ok(Item::Import(import)));
+    }
+    if let Some(decl) = ConstDecl::parse(line) {
+        return Some(Ok(Item::Const(decl)));
+    }
+    if let Some(signature) = Signature::parse(line) {
+        return Some(Ok(Item::Signature(signature)));
+    }
+    let code = split_inline_comment(line).0.trim();
+    if let Some(path) = code.strip_prefix("pub use ") {
+        return Some(Ok(Item::ModernReexport(path.trim())));
+    }
+
+    let (public, body) = if let Some(body) = code.strip_prefix("export.") {
+        (true, body)
+    } else {
+        (false, code.strip_prefix("proc.")?)
+    };
+    if public && body.contains("::") {
+        return Some(Ok(Item::LegacyReexport(body)));
+    }
+    let parts: Vec<&str> = body.split('.').collect();
+    let valid_name = |name: &str| !name.is_empty() && !name.contains(char::is_whitespace);
+    match parts.as_slice() {
+        [name] if valid_name(name) => Some(Ok(Item::LegacyProc(LegacyProc {
+            public,
+            name,
+            locals: None,
+        }))),
+        [name, locals] if valid_name(name) && locals.parse::<u16>().is_ok() => {
+            Some(Ok(Item::LegacyProc(LegacyProc {
+                public,
+                name,
+                locals: Some(locals),
+            })))
+        }
+        _ => Some(Err(format!(
+            "cannot migrate procedure declaration `{code}`"
+        ))),
+    }
+}
+
+fn with_comment(line: String, comment: &str) -> String {
+    if comment.is_empty() {
+        line
+    } else {
+        format!("{line} {comment}")
+    }
+}
+
+/// Rewrites every declaration of a module into the `to` dialect. Lines covered by a formatter
+/// directive are left as written and reported if they would have been rewritten.
+pub(crate) fn migrate(code: &str, to: Syntax) -> Migration {
+    let lines: Vec<&str> = code.lines().collect();
+    let protected = protected_lines(&lines);
+    let mut result: Vec<String> = Vec::with_capacity(lines.len());
+    let mut diagnostics = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let column = indent.chars().count() + 1;
+        let (code, comment) = split_inline_comment(line.trim());
+        let code = code.trim();
+        let item = if is_comment(line) {
+            None
+        } else {
+            parse_item(line.trim())
+        };
+
+        let item = match item {
+            None => {
+                result.push(line.to_string());
+                continue;
+            }
+            Some(Err(message)) => {
+                if !protected[i] {
+                    diagnostics.push(Diagnostic::error(i + 1, column, message));
+                }
+                result.push(line.to_string());
+                continue;
+            }
+            Some(Ok(_)) if protected[i] && declaration_syntax(line) == Some(to) => {
+                result.push(line.to_string());
+                continue;
+            }
+            Some(Ok(_)) if protected[i] => {
+                diagnostics.push(Diagnostic::warning(
+                    i + 1,
+                    column,
+                    "line not migrated: it is covered by a formatter directive",
+                ));
+                result.push(line.to_string());
+                continue;
+            }
+            Some(Ok(item)) => item,
+        };
+
+        let migrated = match (item, to) {
+            (Item::Import(mut import), _) => {
+                import.syntax = to;
+                import.render()
+            }
+            (Item::Const(mut decl), _) => {
+                decl.syntax = to;
+                decl.render()
+            }
+            (Item::LegacyProc(decl), Syntax::Modern) => {
+                if let Some(locals) = decl.locals {
+                    let has_locals_attribute = result
+                        .iter()
+                        .rev()
+                        .take_while(|line| is_decorator(line))
+                        .any(|line| line.trim().starts_with("@locals"));
+                    if has_locals_attribute {
+                        diagnostics.push(Diagnostic::error(
+                            i + 1,
+                            column,
+                            format!("`{code}` declares its locals twice"),
+                        ));
+                        result.push(line.to_string());
+                        continue;
+                    }
+                    result.push(format!("{indent}@locals({locals})"));
+                }
+                let keyword = if decl.public { "pub proc" } else { "proc" };
+                with_comment(format!("{keyword} {}", decl.name), comment)
+            }
+            (Item::LegacyReexport(path), Syntax::Modern) => {
+                with_comment(format!("pub use {path}"), comment)
+            }
+            (Item::ModernReexport(path), Syntax::Legacy) => {
+                with_comment(format!("export.{path}"), comment)
+            }
+            (Item::Signature(signature), Syntax::Legacy) => {
+                let typed = signature
+                    .params
+                    .as_ref()
+                    .is_some_and(|params| !params.is_empty())
+                    || signature.return_type.is_some();
+                if typed {
+                    diagnostics.push(
+                        Diagnostic::error(
+                            i + 1,
+                            column,
+                            format!("cannot migrate typed signature of `{}`", signature.name),
+                        )
+                        .with_help("the legacy syntax has no parameter or return types"),
+                    );
+                    result.push(line.to_string());
+                    continue;
+                }
+                let keyword = if signature.public { "export" } else { "proc" };
+                let mut declaration = format!("{keyword}.{}", signature.name);
+                let attributes = result
+                    .iter()
+                    .rev()
+                    .take_while(|line| is_decorator(line))
+                    .count();
+                let first_attribute = result.len() - attributes;
+                if let Some(offset) = result[first_attribute..]
+                    .iter()
+                    .position(|line| locals_attribute(line).is_some())
+                {
+                    let attribute = result.remove(first_attribute + offset);
+                    let locals = locals_attribute(&attribute).unwrap_or_default();
+                    declaration = format!("{declaration}.{locals}");
+                }
+                with_comment(declaration, comment)
+            }
+            // Already in the target dialect.
+            _ => {
+                result.push(line.to_string());
+                continue;
+            }
+        };
+        result.push(format!("{indent}{migrated}"));
+    }
+
+    let mut code: String = result.join("\n");
+    code.push('\n');
+    Migration { code, diagnostics }
+}
+
+/// Returns the dialect a declaration line is written in, if it is a declaration whose form
+/// differs between the dialects.
+fn declaration_syntax(line: &str) -> Option<Syntax> {
+    match parse_item(line.trim())? {
+        Ok(Item::Import(import)) => Some(import.syntax),
+        Ok(Item::Const(decl)) => Some(decl.syntax),
+        Ok(Item::ModernReexport(_) | Item::Signature(_)) => Some(Syntax::Modern),
+        Ok(Item::LegacyProc(_) | Item::LegacyReexport(_)) | Err(_) => Some(Syntax::Legacy),
+    }
+}
+
+/// Returns true if the line declares a procedure or a re-export, the declarations that decide
+/// the dialect of a module.
+fn declares_procedure(line: &str) -> bool {
+    !matches!(
+        parse_item(line.trim()),
+        None | Some(Ok(Item::Import(_) | Item::Const(_)))
+    )
+}
+
+/// Reports procedure and re-export declarations that are not in the `expected` dialect. Without
+/// an expected dialect, the dialect of the first such declaration is expected, so that modules
+/// mixing both are reported. Lines covered by a formatter directive are not checked.
+pub(crate) fn check_syntax(code: &str, expected: Option<Syntax>) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = code.lines().collect();
+    let protected = protected_lines(&lines);
+    let mut expected = expected.map(|syntax| (syntax, None));
+    let mut diagnostics = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if protected[i] || is_comment(line) || !declares_procedure(line) {
+            continue;
+        }
+        let Some(syntax) = declaration_syntax(line) else {
+            continue;
+        };
+        let Some((expected_syntax, first_line)) = expected else {
+            expected = Some((syntax, Some(i + 1)));
+            continue;
+        };
+        if syntax == expected_syntax {
+            continue;
+        }
+
+        let column = line.len() - line.trim_start().len() + 1;
+        let declaration = split_inline_comment(line).0.trim();
+        let diagnostic = match first_line {
+            Some(first_line) => Diagnostic::error(
+                i + 1,
+                column,
+                format!(
+                    "`{declaration}` uses the {} syntax, but line {first_line} uses the {} syntax",
+                    syntax.name(),
+                    expected_syntax.name(),
+                ),
+            ),
+            None => Diagnostic::error(
+                i + 1,
+                column,
+                format!(
+                    "`{declaration}` uses the {} syntax, but the configured syntax is {}",
+                    syntax.name(),
+                    expected_syntax.name(),
+                ),
+            ),
+        };
+        diagnostics.push(diagnostic.with_help(format!(
+            "run `masm-fmt migrate --to {}` to convert the module",
+            expected_syntax.name()
+        )));
+    }
+
+    diagnostics
+}
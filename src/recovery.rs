@@ -0,0 +1,123 @@
+//! Detection of the parts of a module whose block structure is broken, such as a procedure
+//! missing its `end`, so that the rest of the module can still be formatted.
+
+use std::ops::Range;
+
+use crate::{
+    ConstructType, diagnostics::Diagnostic, directives::protected_lines, is_comment, is_decorator,
+    is_proc_or_export, leading_word, line_constructs, strip_inline_comment,
+};
+
+/// A region of lines that cannot be formatted, and the finding explaining why. Regions opened by
+/// lines covered by a formatter directive are left as written without a finding.
+pub(crate) struct BrokenRegion {
+    pub lines: Range<usize>,
+    pub diagnostic: Option<Diagnostic>,
+}
+
+/// Returns the index of the first of the comment and attribute lines directly above `line`.
+fn attached_start(lines: &[&str], line: usize) -> usize {
+    let mut start = line;
+    while start > 0 && (is_comment(lines[start - 1]) || is_decorator(lines[start - 1])) {
+        start -= 1;
+    }
+    start
+}
+
+/// Returns true if the line starts a top-level item: a procedure, a program, an import, a
+/// constant or an advice map entry written at the start of the line.
+fn starts_item(line: &str) -> bool {
+    if is_proc_or_export(line) {
+        return true;
+    }
+    let code = strip_inline_comment(line);
+    !line.starts_with(char::is_whitespace)
+        && (leading_word(code) == Some("begin")
+            || ["use.", "use ", "pub use ", "const.", "const ", "adv_map."]
+                .iter()
+                .any(|prefix| code.starts_with(prefix)))
+}
+
+fn column(line: &str) -> usize {
+    line.chars().take_while(|c| c.is_whitespace()).count() + 1
+}
+
+fn unclosed(lines: &[&str], opener: usize, start: usize, end: usize, report: bool) -> BrokenRegion {
+    let declaration = strip_inline_comment(lines[opener]);
+    BrokenRegion {
+        lines: start..end,
+        diagnostic: report.then(|| {
+            Diagnostic::error(
+                opener + 1,
+                column(lines[opener]),
+                format!("`{declaration}` is never closed with `end`"),
+            )
+            .with_help("the block was left unformatted")
+        }),
+    }
+}
+
+/// Finds the top-level items whose blocks are not balanced. The start of another top-level item
+/// inside an open block means the block before it is missing an `end`, and an `end` outside any
+/// block is unmatched. Each broken item spans from its attached comments to the line before the
+/// next item.
+///
+/// Lines covered by a formatter directive count towards the block structure, as they do when
+/// formatting, but never start the next item and are not reported.
+pub(crate) fn broken_regions(lines: &[&str]) -> Vec<BrokenRegion> {
+    let protected = protected_lines(lines);
+    let mut regions = Vec::new();
+    let mut depth = 0usize;
+    // The line opening the current top-level item, and the first line of the item.
+    let mut item: Option<(usize, usize)> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        if !protected[i]
+            && starts_item(line)
+            && depth > 0
+            && let Some((opener, start)) = item
+        {
+            let end = attached_start(lines, i);
+            regions.push(unclosed(lines, opener, start, end, !protected[opener]));
+            depth = 0;
+        }
+
+        for construct in line_constructs(line) {
+            match construct {
+                ConstructType::End if depth == 0 => {
+                    if !protected[i] {
+                        regions.push(BrokenRegion {
+                            lines: i..i + 1,
+                            diagnostic: Some(
+                                Diagnostic::error(i + 1, column(line), "unmatched `end`")
+                                    .with_help("the line was left unformatted"),
+                            ),
+                        });
+                    }
+                }
+                ConstructType::End => depth -= 1,
+                ConstructType::Else => {}
+                _ => {
+                    if depth == 0 {
+                        item = Some((i, attached_start(lines, i)));
+                    }
+                    depth += 1;
+                }
+            }
+        }
+    }
+
+    if depth > 0
+        && let Some((opener, start)) = item
+    {
+        regions.push(unclosed(
+            lines,
+            opener,
+            start,
+            lines.len(),
+            !protected[opener],
+        ));
+    }
+
+    regions
+}
@@ -0,0 +1,198 @@
+//! Re-exports of procedures defined in other modules: `export.std::sys::truncate_stack` in the
+//! legacy syntax, `pub use std::sys::truncate_stack` in the modern one, both optionally renamed
+//! with `->name`.
+
+use crate::{
+    ConstructType,
+    config::{ReexportConfig, Syntax},
+    directives::protected_lines,
+    is_comment, is_section_separator_comment, line_constructs, split_inline_comment,
+};
+
+/// A single re-export.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Reexport {
+    pub syntax: Syntax,
+    /// Path of the re-exported procedure, e.g. `::miden::util::account_id::is_equal`.
+    pub path: String,
+    /// Name given with `->`, if any.
+    pub alias: Option<String>,
+    /// Inline comment following the re-export, including the `#`.
+    pub comment: Option<String>,
+}
+
+impl Reexport {
+    pub fn parse(line: &str) -> Option<Self> {
+        let (code, comment) = split_inline_comment(line.trim());
+        let (syntax, code) = if let Some(code) = code.strip_prefix("export.")
+            && (code.contains("::") || code.contains("->"))
+        {
+            (Syntax::Legacy, code)
+        } else {
+            (Syntax::Modern, code.strip_prefix("pub use ")?)
+        };
+
+        let (path, alias) = match code.split_once("->") {
+            Some((path, alias)) => (path, Some(alias.trim().to_string())),
+            None => (code, None),
+        };
+        let path = path
+            .split("::")
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("::");
+        if path.is_empty() || alias.as_deref() == Some("") {
+            return None;
+        }
+
+        Some(Self {
+            syntax,
+            path,
+            alias,
+            comment: (!comment.is_empty()).then(|| comment.trim().to_string()),
+        })
+    }
+
+    /// Renders the re-export with no spaces around `::` and `->`.
+    pub fn render(&self) -> String {
+        let mut line = match self.syntax {
+            Syntax::Legacy => format!("export.{}", self.path),
+            Syntax::Modern => format!("pub use {}", self.path),
+        };
+        if let Some(alias) = &self.alias {
+            line.push_str("->");
+            line.push_str(alias);
+        }
+        if let Some(comment) = &self.comment {
+            line.push(' ');
+            line.push_str(comment);
+        }
+        line
+    }
+
+    /// The name the procedure is exported under: the alias if one is given, otherwise the last
+    /// path segment.
+    pub fn name(&self) -> &str {
+        self.alias
+            .as_deref()
+            .unwrap_or_else(|| self.path.rsplit("::").next().unwrap_or(&self.path))
+    }
+}
+
+/// Rewrites every top-level re-export with canonical spacing around `::` and `->`.
+pub(crate) fn normalize_reexports(lines: Vec<String>) -> Vec<String> {
+    let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let protected = protected_lines(&line_refs);
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let top_level = !line.starts_with(char::is_whitespace);
+            match Reexport::parse(line) {
+                Some(reexport) if top_level && !protected[i] => reexport.render(),
+                _ => line.clone(),
+            }
+        })
+        .collect()
+}
+
+/// A re-export together with the comment lines directly above it.
+struct Item<'a> {
+    comments: Vec<&'a str>,
+    line: &'a str,
+    reexport: Reexport,
+}
+
+/// Gathers all top-level re-exports of a module into a single section, placed where the first
+/// re-export was, and sorts them by exported name. Comment lines directly above a re-export move
+/// with it, unless they start the module and so form its header. Items are separated by an empty
+/// line if any of them has comments; blank lines between gathered items are dropped, so the
+/// section is rebuilt the same way on every run.
+pub(crate) fn sort_reexports(lines: &[&str], config: &ReexportConfig) -> Vec<String> {
+    if !config.sort {
+        return lines.iter().map(|line| line.to_string()).collect();
+    }
+
+    let protected = protected_lines(lines);
+    let mut items: Vec<Item> = Vec::new();
+    let mut taken = vec![false; lines.len()];
+    let mut section_start = None;
+    let mut previous_end = None;
+    let mut depth = 0usize;
+
+    for (i, line) in lines.iter().enumerate() {
+        for construct in line_constructs(line) {
+            match construct {
+                ConstructType::End => depth = depth.saturating_sub(1),
+                ConstructType::Else => {}
+                _ => depth += 1,
+            }
+        }
+        if depth > 0 || protected[i] {
+            continue;
+        }
+        let Some(reexport) = Reexport::parse(line) else {
+            continue;
+        };
+
+        let mut first = i;
+        while first > 0
+            && !taken[first - 1]
+            && !protected[first - 1]
+            && is_comment(lines[first - 1])
+            && !is_section_separator_comment(lines[first - 1])
+        {
+            first -= 1;
+        }
+        if first == 0 {
+            first = i;
+        }
+        section_start.get_or_insert(first);
+        for flag in &mut taken[first..=i] {
+            *flag = true;
+        }
+        if let Some(previous_end) = previous_end {
+            let gap = previous_end + 1..first;
+            if lines[gap.clone()].iter().all(|line| line.trim().is_empty()) {
+                for flag in &mut taken[gap] {
+                    *flag = true;
+                }
+            }
+        }
+        previous_end = Some(i);
+        items.push(Item {
+            comments: lines[first..i].to_vec(),
+            line,
+            reexport,
+        });
+    }
+
+    let Some(section_start) = section_start else {
+        return lines.iter().map(|line| line.to_string()).collect();
+    };
+    items.sort_by(|a, b| {
+        (a.reexport.name(), &a.reexport.path).cmp(&(b.reexport.name(), &b.reexport.path))
+    });
+    let spaced = items.iter().any(|item| !item.comments.is_empty());
+
+    let mut section: Vec<String> = Vec::new();
+    for (n, item) in items.iter().enumerate() {
+        if spaced && n > 0 {
+            section.push(String::new());
+        }
+        section.extend(item.comments.iter().map(|line| line.to_string()));
+        section.push(item.line.to_string());
+    }
+
+    let mut result = Vec::with_capacity(lines.len() + items.len());
+    for (i, line) in lines.iter().enumerate() {
+        if i == section_start {
+            result.append(&mut section);
+        }
+        if !taken[i] {
+            result.push(line.to_string());
+        }
+    }
+    result
+}
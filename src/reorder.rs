@@ -0,0 +1,180 @@
+//! Reordering of the procedures of a module.
+
+use crate::{
+    ConstructType,
+    config::{ProcedureConfig, ProcedureOrder},
+    directives::{Directive, parse_directive, protected_lines},
+    is_comment, is_decorator, is_proc_or_export, is_section_separator_comment, leading_word,
+    line_constructs,
+    signatures::Signature,
+    strip_inline_comment,
+};
+
+/// A top-level procedure or program block, with the comments and attributes directly above it.
+struct Item {
+    /// Index of the first line, including comments and attributes.
+    start: usize,
+    /// Index one past the `end` of the block.
+    end: usize,
+    /// Index of the declaration line.
+    declaration: usize,
+}
+
+/// Returns whether a procedure declaration is exported, and its name.
+fn declaration_name(line: &str) -> Option<(bool, String)> {
+    let code = strip_inline_comment(line);
+    if let Some(signature) = Signature::parse(code) {
+        return Some((signature.public, signature.name));
+    }
+    let (public, body) = match code.strip_prefix("export.") {
+        Some(body) => (true, body),
+        None => (false, code.strip_prefix("proc.")?),
+    };
+    Some((public, body.split('.').next()?.to_string()))
+}
+
+/// Splits the module into its top-level procedures and program blocks. Returns `None` if a block
+/// is never closed.
+fn find_items(lines: &[&str]) -> Option<Vec<Item>> {
+    let mut items = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let constructs = line_constructs(line);
+        let opens_item =
+            is_proc_or_export(line) || leading_word(strip_inline_comment(line)) == Some("begin");
+        if !opens_item || constructs.is_empty() {
+            // A line at the top level that is neither; blocks are skipped whole below.
+            if constructs
+                .first()
+                .is_some_and(|construct| *construct != ConstructType::End)
+            {
+                return None;
+            }
+            i += 1;
+            continue;
+        }
+
+        let mut start = i;
+        while start > 0
+            && (is_decorator(lines[start - 1])
+                || (is_comment(lines[start - 1])
+                    && !is_section_separator_comment(lines[start - 1])))
+        {
+            start -= 1;
+        }
+
+        let mut depth = 0usize;
+        let mut end = None;
+        for (j, line) in lines.iter().enumerate().skip(i) {
+            for construct in line_constructs(line) {
+                match construct {
+                    ConstructType::End => depth = depth.saturating_sub(1),
+                    ConstructType::Else => {}
+                    _ => depth += 1,
+                }
+            }
+            if depth == 0 {
+                end = Some(j + 1);
+                break;
+            }
+        }
+        let end = end?;
+        items.push(Item {
+            start,
+            end,
+            declaration: i,
+        });
+        i = end;
+    }
+
+    Some(items)
+}
+
+/// Moves whole procedures, with their doc comments and attributes, into the configured order.
+/// Procedures take each other's places, so comments and other items between procedures stay
+/// where they are; program blocks move to the end of the module. Items covered by a
+/// `masm-fmt: skip` directive keep their place, and the module is left as written if it holds a
+/// `masm-fmt: off` region or an unclosed block.
+pub(crate) fn reorder_procedures(lines: &[&str], config: &ProcedureConfig) -> Vec<String> {
+    let unchanged = || lines.iter().map(|line| line.to_string()).collect();
+    if config.order == ProcedureOrder::Preserve && !config.alphabetical {
+        return unchanged();
+    }
+    if lines
+        .iter()
+        .any(|line| parse_directive(line) == Some(Directive::Off))
+    {
+        return unchanged();
+    }
+    let Some(mut items) = find_items(lines) else {
+        return unchanged();
+    };
+    // Skipped items are left among the lines that are not moved.
+    let protected = protected_lines(lines);
+    items.retain(|item| !protected[item.declaration]);
+
+    let (programs, mut procedures): (Vec<&Item>, Vec<&Item>) = items
+        .iter()
+        .partition(|item| declaration_name(lines[item.declaration]).is_none());
+    let key = |item: &Item| {
+        let (public, name) = declaration_name(lines[item.declaration]).unwrap_or_default();
+        let group = match config.order {
+            ProcedureOrder::Preserve => 0,
+            ProcedureOrder::HelpersFirst => usize::from(public),
+            ProcedureOrder::ExportsFirst => usize::from(!public),
+        };
+        (
+            group,
+            if config.alphabetical {
+                name
+            } else {
+                String::new()
+            },
+        )
+    };
+    let slots: Vec<&Item> = procedures.clone();
+    procedures.sort_by_cached_key(|item| key(item));
+
+    let mut result = Vec::with_capacity(lines.len() + 1);
+    let mut slot = 0;
+    let mut i = 0;
+    while i < lines.len() {
+        if let Some(item) = slots.get(slot)
+            && item.start == i
+        {
+            result.extend(
+                lines[procedures[slot].start..procedures[slot].end]
+                    .iter()
+                    .map(|line| line.to_string()),
+            );
+            slot += 1;
+            i = item.end;
+        } else if let Some(program) = programs.iter().find(|program| program.start == i) {
+            i = program.end;
+            // Drop the empty lines the program leaves behind.
+            if result
+                .last()
+                .is_none_or(|line: &String| line.trim().is_empty())
+            {
+                while i < lines.len() && lines[i].trim().is_empty() {
+                    i += 1;
+                }
+            }
+        } else {
+            result.push(lines[i].to_string());
+            i += 1;
+        }
+    }
+
+    for program in programs {
+        result.push(String::new());
+        result.extend(
+            lines[program.start..program.end]
+                .iter()
+                .map(|line| line.to_string()),
+        );
+    }
+    result
+}
@@ -0,0 +1,262 @@
+//! Procedure declarations in the modern syntax, with typed parameters:
+//! `pub proc gt(rhs: word, lhs: word) -> i1`.
+
+use crate::{INDENT, directives::protected_lines, split_inline_comment};
+
+/// A procedure declaration in the modern syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Signature {
+    pub public: bool,
+    pub name: String,
+    /// Parameters as `name: type`, or `None` if the declaration has no parameter list.
+    pub params: Option<Vec<String>>,
+    pub return_type: Option<String>,
+    /// Inline comment following the declaration, including the `#`.
+    pub comment: Option<String>,
+}
+
+/// Collapses runs of whitespace into single spaces and removes spaces before commas and closing
+/// brackets and after opening ones.
+fn normalize_spacing(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+    {
+        if matches!(c, ',' | ')' | ']' | '>') && out.ends_with(' ') {
+            out.pop();
+        }
+        if c == ' ' && out.ends_with([' ', '(', '[', '<']) {
+            continue;
+        }
+        out.push(c);
+        if c == ',' {
+            out.push(' ');
+        }
+    }
+    out.trim_end().to_string()
+}
+
+/// Splits a list on the commas that are not nested in brackets or string literals.
+pub(crate) fn split_top_level(list: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, c) in list.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '(' | '[' | '<' => depth += 1,
+            ')' | ']' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&list[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&list[start..]);
+    parts
+}
+
+/// Returns the index of the parenthesis closing the one at `open`, if any.
+pub(crate) fn matching_paren(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0i32;
+    for (i, c) in text[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+impl Signature {
+    pub fn parse(line: &str) -> Option<Self> {
+        let (code, comment) = split_inline_comment(line.trim());
+        let code = code.trim();
+        let (public, rest) = match code.strip_prefix("pub") {
+            Some(rest) if rest.starts_with(char::is_whitespace) => (true, rest.trim_start()),
+            _ => (false, code),
+        };
+        let rest = rest.strip_prefix("proc")?;
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let rest = rest.trim_start();
+
+        let name_end = rest
+            .find(|c: char| c == '(' || c == '-' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        if name.is_empty() {
+            return None;
+        }
+        let mut rest = rest[name_end..].trim_start();
+
+        let params = if rest.starts_with('(') {
+            let close = matching_paren(rest, 0)?;
+            let inner = &rest[1..close];
+            rest = rest[close + 1..].trim_start();
+            let params: Vec<String> = split_top_level(inner)
+                .into_iter()
+                .map(str::trim)
+                .filter(|param| !param.is_empty())
+                .map(|param| match param.split_once(':') {
+                    Some((name, ty)) => {
+                        format!("{}: {}", name.trim(), normalize_spacing(ty.trim()))
+                    }
+                    None => normalize_spacing(param),
+                })
+                .collect();
+            Some(params)
+        } else {
+            None
+        };
+
+        let return_type = if let Some(ret) = rest.strip_prefix("->") {
+            let ret = ret.trim();
+            if ret.is_empty() {
+                return None;
+            }
+            Some(normalize_spacing(ret))
+        } else if rest.is_empty() {
+            None
+        } else {
+            return None;
+        };
+
+        Some(Self {
+            public,
+            name: name.to_string(),
+            params,
+            return_type,
+            comment: (!comment.is_empty()).then(|| comment.trim().to_string()),
+        })
+    }
+
+    fn head(&self) -> String {
+        if self.public {
+            format!("pub proc {}", self.name)
+        } else {
+            format!("proc {}", self.name)
+        }
+    }
+
+    fn tail(&self) -> String {
+        let mut tail = String::new();
+        if let Some(return_type) = &self.return_type {
+            tail.push_str(" -> ");
+            tail.push_str(return_type);
+        }
+        if let Some(comment) = &self.comment {
+            tail.push(' ');
+            tail.push_str(comment);
+        }
+        tail
+    }
+
+    /// Renders the declaration on a single line.
+    pub fn render(&self) -> String {
+        let params = match &self.params {
+            Some(params) => format!("({})", params.join(", ")),
+            None => String::new(),
+        };
+        format!("{}{params}{}", self.head(), self.tail())
+    }
+
+    /// Renders the declaration with one parameter per line and a trailing comma.
+    pub fn render_wrapped(&self, indent: &str) -> Vec<String> {
+        let mut lines = vec![format!("{indent}{}(", self.head())];
+        for param in self.params.iter().flatten() {
+            lines.push(format!("{indent}{INDENT}{param},"));
+        }
+        lines.push(format!("{indent}){}", self.tail()));
+        lines
+    }
+}
+
+/// Joins procedure declarations whose parameter list spans several lines into a single line, so
+/// that the formatter can treat each declaration as one line.
+pub(crate) fn join_signatures(lines: &[&str]) -> Vec<String> {
+    let protected = protected_lines(lines);
+    let mut result = Vec::with_capacity(lines.len());
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        let code = split_inline_comment(line).0.trim();
+        let opens_signature = !protected[i]
+            && (code.starts_with("proc ") || code.starts_with("pub proc "))
+            && code.contains('(')
+            && matching_paren(code, code.find('(').unwrap()).is_none();
+
+        if opens_signature {
+            let mut joined = code.to_string();
+            let mut end = i + 1;
+            while end < lines.len() && !protected[end] {
+                joined.push(' ');
+                joined.push_str(lines[end].trim());
+                end += 1;
+                let open = joined.find('(').unwrap();
+                if matching_paren(&joined, open).is_some() {
+                    break;
+                }
+            }
+            if let Some(signature) = Signature::parse(&joined) {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                result.push(format!("{indent}{}", signature.render()));
+                i = end;
+                continue;
+            }
+        }
+
+        result.push(line.to_string());
+        i += 1;
+    }
+
+    result
+}
+
+/// Formats procedure declarations with canonical spacing around `:`, `,` and `->`, wrapping the
+/// parameter list one per line when the declaration is wider than `max_width`.
+pub(crate) fn format_signatures(lines: Vec<String>, max_width: usize) -> Vec<String> {
+    let line_refs: Vec<&str> = lines.iter().map(String::as_str).collect();
+    let protected = protected_lines(&line_refs);
+    let mut result = Vec::with_capacity(lines.len());
+
+    for (i, line) in lines.iter().enumerate() {
+        let signature = if protected[i] {
+            None
+        } else {
+            Signature::parse(line)
+        };
+        let Some(signature) = signature else {
+            result.push(line.clone());
+            continue;
+        };
+
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let single_line = format!("{indent}{}", signature.render());
+        let has_params = signature
+            .params
+            .as_ref()
+            .is_some_and(|params| !params.is_empty());
+        if single_line.chars().count() > max_width && has_params {
+            result.extend(signature.render_wrapped(indent));
+        } else {
+            result.push(single_line);
+        }
+    }
+
+    result
+}
@@ -0,0 +1,56 @@
+use masm_formatter::{FormatConfig, check_attributes, format_code, format_code_with_config};
+
+#[test]
+fn test_locals_move_into_attribute() {
+    let config = FormatConfig::from_toml("[procedures]\nlocals = \"attribute\"\n").unwrap();
+    let input = "#! Does things.\nexport.foo.2 # main\nloc_store.0\nend\n\n@locals(1)\nproc.bar\nnop\nend\n";
+    let expected_output = "#! Does things.\n@locals(2)\nexport.foo # main\n    loc_store.0\nend\n\n@locals(1)\nproc.bar\n    nop\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_locals_move_into_suffix() {
+    let config = FormatConfig::from_toml("[procedures]\nlocals = \"suffix\"\n").unwrap();
+    let input = "#! Does things.\n@locals(2)\nexport.foo # main\nloc_store.0\nend\n\n@locals(1)\npub proc bar\nnop\nend\n";
+    let expected_output = "#! Does things.\nexport.foo.2 # main\n    loc_store.0\nend\n\n@locals(1)\npub proc bar\n    nop\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_attribute_spacing() {
+    let input = "@callconv( \"felt\" ,1 ) # abi\n@locals( 2 )\n\nexport.foo\nnop\nend\n";
+    let expected_output = "@callconv(\"felt\", 1) # abi\n@locals(2)\nexport.foo\n    nop\nend\n";
+    assert_eq!(format_code(input), expected_output);
+}
+
+#[test]
+fn test_attribute_order() {
+    let config =
+        FormatConfig::from_toml("[procedures]\nattribute_order = [\"locals\", \"callconv\"]\n")
+            .unwrap();
+    let input = "@inline\n@callconv(\"felt\")\n@locals(2)\nproc foo\nnop\nend\n";
+    let expected_output = "@locals(2)\n@callconv(\"felt\")\n@inline\nproc foo\n    nop\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_detached_attributes_are_reported() {
+    let input = "@locals(2)\n\nexport.foo\n    nop\nend\n\n@locals(1)\n# stray\nproc.bar\n    nop\nend\n\n@inline\n";
+    assert_eq!(
+        check_attributes(input)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec![
+            "2:1: error: empty line between attribute `@locals(2)` and its procedure",
+            "8:1: error: comment between attribute `@locals(1)` and its procedure",
+            "13:1: error: attribute `@inline` does not decorate a procedure",
+        ]
+    );
+}
+
+#[test]
+fn test_attributes_under_directives_are_not_checked() {
+    let input = "# masm-fmt: off\n@locals(2)\n\nexport.foo\n    nop\nend\n# masm-fmt: on\n\n# masm-fmt: skip\n@inline\n";
+    assert!(check_attributes(input).is_empty());
+}
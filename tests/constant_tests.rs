@@ -0,0 +1,146 @@
+use std::fs;
+
+use masm_formatter::{
+    Diagnostic, FormatConfig, check_constants, check_constants_with_resolver, format_code,
+    format_code_with_config,
+};
+
+fn aligned_config() -> FormatConfig {
+    FormatConfig::from_toml("[constants]\nalign = true\n").expect("valid config")
+}
+
+#[test]
+fn test_align_consecutive_constants() {
+    let input = "const.FOO=1\nconst.LONGER_NAME=0x1234\nconst X = 2\nconst LONG = 3\n\nconst.A=1 # one\nconst.BB=0x10 # sixteen\n# comment\nconst.ALONE=3\n\nbegin\nend";
+    let expected_output = "const.FOO=        1\nconst.LONGER_NAME=0x1234\nconst X    = 2\nconst LONG = 3\n\nconst.A= 1 # one\nconst.BB=0x10 # sixteen\n# comment\nconst.ALONE=3\n\nbegin\nend\n";
+    assert_eq!(
+        format_code_with_config(input, &aligned_config()),
+        expected_output
+    );
+    assert_eq!(
+        format_code_with_config(expected_output, &aligned_config()),
+        expected_output
+    );
+}
+
+#[test]
+fn test_align_constants_is_opt_in() {
+    let input = "const.FOO=1\nconst.LONGER_NAME=0x1234\n\nbegin\nend\n";
+    assert_eq!(format_code(input), input);
+}
+
+#[test]
+fn test_align_constants_respects_off_region() {
+    let input = "# masm-fmt: off\nconst.A=1\nconst.LONG=2\n# masm-fmt: on\n\nbegin\nend\n";
+    assert_eq!(format_code_with_config(input, &aligned_config()), input);
+}
+
+#[test]
+fn test_normalize_constant_expressions() {
+    let config = FormatConfig::from_toml("[constants]\nnormalize = true\n").unwrap();
+    let input = "const.X=A+B*2\nconst.Y = A + B*2 # note\nconst Z=A+ B\nconst W=-(A//2)\nconst.V=[1,2 ,3,4]\nconst.E=event(\"a+b\")\n\nbegin\nend";
+    let expected_output = "const.X=A + B * 2\nconst.Y=A + B * 2 # note\nconst Z = A + B\nconst W = -(A // 2)\nconst.V=[1, 2, 3, 4]\nconst.E=event(\"a+b\")\n\nbegin\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_convert_constant_syntax() {
+    let to_modern = FormatConfig::from_toml("[constants]\nsyntax = \"modern\"\n").unwrap();
+    let to_legacy = FormatConfig::from_toml("[constants]\nsyntax = \"legacy\"\n").unwrap();
+    let legacy = "const.A=1\nconst.B=A*2\n\nbegin\nend\n";
+    let modern = "const A = 1\nconst B = A*2\n\nbegin\nend\n";
+    assert_eq!(format_code_with_config(legacy, &to_modern), modern);
+    assert_eq!(format_code_with_config(modern, &to_legacy), legacy);
+}
+
+#[test]
+fn test_comment_above_modern_constant_is_stable() {
+    let config = FormatConfig::from_toml("[constants]\nsyntax = \"modern\"\n").unwrap();
+    let input = "# Mem\nconst.A=1\n\nbegin\nend";
+    let expected_output = "# Mem\nconst A = 1\n\nbegin\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+    assert_eq!(
+        format_code_with_config(expected_output, &config),
+        expected_output
+    );
+}
+
+fn messages(diagnostics: Vec<Diagnostic>) -> Vec<String> {
+    diagnostics.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn test_check_constants_reports_undefined_and_cycles() {
+    let input = "const.A=B+1\nconst.B=A*2\nconst.C=MISSING\nconst.D=(C + 2) // 3\n\nbegin\n    push.D\nend\n";
+    assert_eq!(
+        messages(check_constants(input)),
+        vec![
+            "1:7: error: cyclic constant definition: A -> B -> A",
+            "3:9: error: undefined constant `MISSING`",
+        ]
+    );
+}
+
+#[test]
+fn test_check_constants_reports_field_and_u32_overflow() {
+    let input = "const.BIG=0xFFFFFFFF00000001\nconst.WRAP=0xFFFFFFFF00000000+2\nconst.ADDR=0x100000000\nconst.OK=0xFFFFFFFF\n\nbegin\n    mem_load.ADDR u32lt.OK\n    push.ADDR\nend\n";
+    assert_eq!(
+        messages(check_constants(input)),
+        vec![
+            "1:11: error: literal `0xFFFFFFFF00000001` exceeds the field modulus",
+            "2:7: warning: the value of `WRAP` overflows the field modulus and wraps around",
+            "7:14: warning: `ADDR` is used as a u32 immediate of `mem_load`, but its value 4294967296 exceeds u32::MAX",
+        ]
+    );
+}
+
+#[test]
+fn test_u32_immediates_only_resolve_constant_names() {
+    let input =
+        "begin\n    u32assert.err=bad u32lt.lower u32lt.MISSING mem_load.other::ADDR\nend\n";
+    assert_eq!(
+        messages(check_constants(input)),
+        vec![
+            "2:41: warning: undefined constant `MISSING`",
+            "2:58: warning: undefined module `other` in `other::ADDR`",
+        ]
+    );
+}
+
+#[test]
+fn test_check_constants_through_imports() {
+    let input = "use.std::math::u64\n\nconst.A=u64::MAX+1\nconst.B=u64::MISSING\nconst.C=other::X\n\nbegin\nend\n";
+    let resolver =
+        |path: &str| (path == "std::math::u64").then(|| "const.MAX=0xFFFFFFFF\n".to_string());
+    assert_eq!(
+        messages(check_constants_with_resolver(input, &resolver)),
+        vec![
+            "4:9: error: undefined constant `u64::MISSING`",
+            "5:9: error: undefined module `other` in `other::X`",
+        ]
+    );
+    assert_eq!(
+        messages(check_constants(input)),
+        vec!["5:9: error: undefined module `other` in `other::X`"]
+    );
+}
+
+#[test]
+fn test_libraries_resolve_relative_to_config_file() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let library_dir = temp_dir.path().join("asm/std/math");
+    fs::create_dir_all(&library_dir).expect("Failed to create library dir");
+    fs::write(library_dir.join("u64.masm"), "const.MAX=0xFFFFFFFF\n").unwrap();
+    let config_path = temp_dir.path().join("masm-fmt.toml");
+    fs::write(
+        &config_path,
+        "[modules]\nlibraries = { std = \"asm/std\" }\n",
+    )
+    .unwrap();
+
+    let config = FormatConfig::from_file(&config_path).unwrap();
+    assert_eq!(
+        config.modules.resolve("std::math::u64").as_deref(),
+        Some("const.MAX=0xFFFFFFFF\n")
+    );
+}
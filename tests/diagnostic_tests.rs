@@ -0,0 +1,47 @@
+use masm_formatter::{Diagnostic, DiagnosticFormat};
+
+const SOURCE: &str = "begin\n    push.1 frobnicate\nend\n";
+
+fn diagnostic() -> Diagnostic {
+    Diagnostic::error(2, 12, "unknown instruction `frobnicate`").with_help("check the spelling")
+}
+
+#[test]
+fn test_plain_rendering_underlines_source() {
+    let expected = "error: unknown instruction `frobnicate`\n --> a.masm:2:12\n  |\n2 |     push.1 frobnicate\n  |            ^^^^^^^^^^\n  |\n  = help: check the spelling\n";
+    assert_eq!(
+        diagnostic().render("a.masm", SOURCE, DiagnosticFormat::Plain),
+        expected
+    );
+}
+
+#[test]
+fn test_rendering_without_source_line_or_help() {
+    let diagnostic = Diagnostic::warning(10, 1, "past the end");
+    assert_eq!(
+        diagnostic.render("a.masm", SOURCE, DiagnosticFormat::Plain),
+        "warning: past the end\n  --> a.masm:10:1\n"
+    );
+}
+
+#[test]
+fn test_colored_rendering_matches_plain_without_escapes() {
+    let colored = diagnostic().render("a.masm", SOURCE, DiagnosticFormat::Colored);
+    assert!(colored.contains("\x1b[1;31merror\x1b[0m"));
+    let stripped = regex::Regex::new("\x1b\\[[0-9;]*m")
+        .unwrap()
+        .replace_all(&colored, "");
+    assert_eq!(
+        stripped,
+        diagnostic().render("a.masm", SOURCE, DiagnosticFormat::Plain)
+    );
+}
+
+#[test]
+fn test_json_rendering() {
+    let diagnostic = Diagnostic::warning(2, 5, "say \"hi\"");
+    assert_eq!(
+        diagnostic.render("dir\\a.masm", SOURCE, DiagnosticFormat::Json),
+        "{\"file\":\"dir\\\\a.masm\",\"line\":2,\"column\":5,\"severity\":\"warning\",\"message\":\"say \\\"hi\\\"\",\"help\":null,\"source\":\"    push.1 frobnicate\"}"
+    );
+}
@@ -1,10 +1,16 @@
+// The example tests predate the current clippy lints on borrowed paths.
+#![allow(clippy::needless_borrow, clippy::needless_borrows_for_generic_args)]
+
 use std::fs;
 use std::fs::read_to_string;
 use std::path::Path;
 use tempfile::tempdir;
 
 // Import the formatting functions from your crate.
-use masm_formatter::{format_code, format_file};
+use masm_formatter::{
+    FormatConfig, check_imports, format_code, format_code_recovering, format_code_with_config,
+    format_file,
+};
 
 fn read_file_to_string(path: &Path) -> String {
     read_to_string(path).expect("Unable to read file")
@@ -201,3 +207,233 @@ fn test_imports_and_file_ending() {
     let expected_output = "use.miden::account\nuse.std::sys\n\nbegin\nend\n";
     assert_eq!(format_code(input), expected_output);
 }
+
+#[test]
+fn test_format_example10() {
+    let input_path = Path::new("tests/unformatted/example10.masm");
+    let expected_output_path = Path::new("tests/expected/example10_formatted.masm");
+
+    let input_code = read_file_to_string(input_path);
+    let expected_output = read_file_to_string(expected_output_path);
+
+    let formatted_code = format_code(&input_code);
+    assert_eq!(formatted_code, expected_output);
+}
+
+#[test]
+fn test_formatter_off_region_is_verbatim() {
+    let input =
+        "begin\n# masm-fmt: off\n  push.1    push.2   \n\n\n  add\n# masm-fmt: on\npush.3\nend";
+    let expected_output = "begin\n    # masm-fmt: off\n  push.1    push.2   \n\n\n  add\n    # masm-fmt: on\n    push.3\nend\n";
+    assert_eq!(format_code(input), expected_output);
+}
+
+#[test]
+fn test_formatter_skip_tracks_indentation() {
+    let input =
+        "# masm-fmt: skip\nproc.foo\n  if.true\n push.1\n  end\nend\n\nproc.bar\npush.1\nend";
+    let expected_output =
+        "# masm-fmt: skip\nproc.foo\n  if.true\n push.1\n  end\nend\n\nproc.bar\n    push.1\nend\n";
+    assert_eq!(format_code(input), expected_output);
+}
+
+#[test]
+fn test_formatter_skip_covers_doc_comment_and_procedure() {
+    let input = "# masm-fmt: skip\n#! Docs.\nproc.foo\npush.1\nend\n\nproc.bar\npush.1\nend";
+    let expected_output =
+        "# masm-fmt: skip\n#! Docs.\nproc.foo\npush.1\nend\n\nproc.bar\n    push.1\nend\n";
+    assert_eq!(format_code(input), expected_output);
+}
+
+#[test]
+fn test_format_example11() {
+    let input_path = Path::new("tests/unformatted/example11.masm");
+    let expected_output_path = Path::new("tests/expected/example11_formatted.masm");
+
+    let input_code = read_file_to_string(input_path);
+    let expected_output = read_file_to_string(expected_output_path);
+
+    let formatted_code = format_code(&input_code);
+    assert_eq!(formatted_code, expected_output);
+}
+
+#[test]
+fn test_hash_inside_string_is_not_a_comment() {
+    let config = FormatConfig::from_toml("[constants]\nnormalize = true\nalign = true\n").unwrap();
+    let input = "use.miden::note\n\nconst.EVENT=event(\"miden::note#created\") # event\nconst.LONGER=1+2 # three\n\nbegin\n    emit.event(\"a#b\")\nend\n";
+    let expected_output = "use.miden::note\n\nconst.EVENT= event(\"miden::note#created\") # event\nconst.LONGER=1 + 2 # three\n\nbegin\n    emit.event(\"a#b\")\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+    assert_eq!(
+        check_imports(input)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec!["1:1: warning: unused import `miden::note`"]
+    );
+}
+
+#[test]
+fn test_inline_blocks_are_tracked() {
+    let input = "proc.foo\nif.true push.1 else push.0 end\nrepeat.4 dup end\ndup if.true\ndrop\nend\nend\n\nbegin\nexec.foo\nend";
+    let expected_output = "proc.foo\n    if.true push.1 else push.0 end\n    repeat.4 dup end\n    dup if.true\n        drop\n    end\nend\n\nbegin\n    exec.foo\nend\n";
+    assert_eq!(format_code(input), expected_output);
+}
+
+#[test]
+fn test_inline_blocks_are_expanded() {
+    let config = FormatConfig::from_toml("[instructions]\ninline_blocks = \"expand\"\n").unwrap();
+    let input = "proc.foo\nif.true push.1 else push.0 end # pick\nrepeat.4 dup end\nemit.event(\"if.true end\")\nend";
+    let expected_output = "proc.foo\n    if.true\n        push.1\n    else\n        push.0\n    end # pick\n    repeat.4\n        dup\n    end\n    emit.event(\"if.true end\")\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_signature_spacing() {
+    let input =
+        "pub proc gt( rhs :word ,lhs:  word )->i1 # compare\ndup\nend\n\nproc  helper\ndrop\nend";
+    let expected_output = "pub proc gt(rhs: word, lhs: word) -> i1 # compare\n    dup\nend\n\nproc helper\n    drop\nend\n";
+    assert_eq!(format_code(input), expected_output);
+}
+
+#[test]
+fn test_long_signature_is_wrapped() {
+    let config = FormatConfig::from_toml("max_width = 40\n").unwrap();
+    let input = "pub proc compare(lhs: word, rhs: word, flags: felt) -> (i1, felt)\ndrop\nend";
+    let expected_output = "pub proc compare(\n    lhs: word,\n    rhs: word,\n    flags: felt,\n) -> (i1, felt)\n    drop\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+    assert_eq!(
+        format_code_with_config(expected_output, &config),
+        expected_output
+    );
+
+    let wide = FormatConfig::default();
+    let joined =
+        "pub proc compare(lhs: word, rhs: word, flags: felt) -> (i1, felt)\n    drop\nend\n";
+    assert_eq!(format_code_with_config(expected_output, &wide), joined);
+}
+
+#[test]
+fn test_blank_line_policy() {
+    let config = FormatConfig::from_toml(
+        "[blank_lines]\nbetween_procedures = 2\nafter_imports = 2\nafter_constants = 0\naround_separators = 1\n",
+    )
+    .unwrap();
+    let input = "use.std::sys\nconst.A=1\n\n#! doc\nexport.foo\n    exec.sys::truncate_stack\nend\n# ===== HELPERS =====\nproc.bar\n    nop\nend\n#! Program.\nbegin\n    exec.foo\nend\n";
+    let expected_output = "use.std::sys\n\n\nconst.A=1\n#! doc\nexport.foo\n    exec.sys::truncate_stack\nend\n\n# ===== HELPERS =====\n\nproc.bar\n    nop\nend\n\n\n#! Program.\nbegin\n    exec.foo\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+    assert_eq!(
+        format_code_with_config(expected_output, &config),
+        expected_output
+    );
+}
+
+#[test]
+fn test_blank_lines_at_block_edges_are_removed() {
+    let config = FormatConfig::from_toml(
+        "[blank_lines]\nremove_after_open = true\nremove_before_end = true\n",
+    )
+    .unwrap();
+    let input = "proc.foo\n\n    if.true\n\n        nop\n    else\n        drop\n\n    end\n\n    dup\n\nend\n";
+    let expected_output =
+        "proc.foo\n    if.true\n        nop\n    else\n        drop\n    end\n\n    dup\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_procedures_are_reordered() {
+    let config =
+        FormatConfig::from_toml("[procedures]\norder = \"helpers-first\"\nalphabetical = true\n")
+            .unwrap();
+    let input = "begin\n    exec.zeta\nend\n\n#! Zeta.\nexport.zeta\n    exec.beta\nend\n\n@locals(1)\nproc.beta\n    nop\nend\n\n#! Alpha.\nexport.alpha\n    nop\nend\n\nproc.aardvark\n    nop\nend\n";
+    let expected_output = "proc.aardvark\n    nop\nend\n\n@locals(1)\nproc.beta\n    nop\nend\n\n#! Alpha.\nexport.alpha\n    nop\nend\n\n#! Zeta.\nexport.zeta\n    exec.beta\nend\n\nbegin\n    exec.zeta\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_skipped_procedures_are_not_reordered() {
+    let config = FormatConfig::from_toml("[procedures]\nalphabetical = true\n").unwrap();
+    let input =
+        "# masm-fmt: skip\nproc.b\n  nop\nend\n\nproc.c\n    nop\nend\n\nproc.a\n    nop\nend\n";
+    let expected_output =
+        "# masm-fmt: skip\nproc.b\n  nop\nend\n\nproc.a\n    nop\nend\n\nproc.c\n    nop\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_word_literals_are_formatted() {
+    let input = "begin\n    push.[ 1,2 ,3,  4 ] dup # => [ 1,2 ]\n    push.[1, 2, 3, 4]\nend\n";
+    let expected_output =
+        "begin\n    push.[1, 2, 3, 4] dup # => [ 1,2 ]\n    push.[1, 2, 3, 4]\nend\n";
+    assert_eq!(format_code(input), expected_output);
+}
+
+#[test]
+fn test_long_advice_map_is_wrapped() {
+    let config = FormatConfig::from_toml("max_width = 40\n").unwrap();
+    let input = "adv_map.KEY = [0x0001,0x0002 , 0x0003,0x0004,0x0005,0x0006] # values\nadv_map.SHORT=[ 1,2 ]\n\nbegin\n    push.1\nend\n";
+    let expected_output = "adv_map.KEY=[\n    0x0001, 0x0002, 0x0003, 0x0004,\n    0x0005, 0x0006\n] # values\nadv_map.SHORT=[1, 2]\n\nbegin\n    push.1\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+    assert_eq!(
+        format_code_with_config(expected_output, &config),
+        expected_output
+    );
+}
+
+#[test]
+fn test_procedure_missing_end_is_left_unformatted() {
+    let input = "proc.first\nnop\nend\n\n#! Broken.\nproc.broken\n  if.true\n  add\n\nexport.last\n      push.1\nend\n";
+    let expected_output = "proc.first\n    nop\nend\n\n#! Broken.\nproc.broken\n  if.true\n  add\n\nexport.last\n    push.1\nend\n";
+    let formatted = format_code_recovering(input, &FormatConfig::default());
+    assert_eq!(formatted.code, expected_output);
+    assert_eq!(formatted.diagnostics.len(), 1);
+    assert_eq!(
+        formatted.diagnostics[0].to_string(),
+        "6:1: error: `proc.broken` is never closed with `end`"
+    );
+    assert_eq!(format_code(expected_output), expected_output);
+}
+
+#[test]
+fn test_unmatched_end_is_reported() {
+    let input = "proc.first\nnop\nend\nend\n\nbegin\n  exec.first\nend\n";
+    let expected_output = "proc.first\n    nop\nend\nend\n\nbegin\n    exec.first\nend\n";
+    let formatted = format_code_recovering(input, &FormatConfig::default());
+    assert_eq!(formatted.code, expected_output);
+    assert_eq!(
+        formatted.diagnostics[0].to_string(),
+        "4:1: error: unmatched `end`"
+    );
+}
+
+#[test]
+fn test_advice_map_comments_are_kept() {
+    let input = "adv_map.A=[ # the values\n    1, 2,\n    3, 4\n]\nadv_map.B=[\n    1, 2\n] # closing\nadv_map.C=[\n    1, # one\n    2\n]\n\nbegin\n    push.1\nend\n";
+    let expected_output = "adv_map.A=[1, 2, 3, 4] # the values\nadv_map.B=[1, 2] # closing\nadv_map.C=[\n    1, # one\n    2\n]\n\nbegin\n    push.1\nend\n";
+    assert_eq!(format_code(input), expected_output);
+}
+
+#[test]
+fn test_items_after_missing_end_are_formatted() {
+    let input = "proc.a\n  push.1\n\nconst.X=1\n\nbegin\n exec.a\nend\n";
+    let expected_output = "proc.a\n  push.1\n\nconst.X=1\n\nbegin\n    exec.a\nend\n";
+    let formatted = format_code_recovering(input, &FormatConfig::default());
+    assert_eq!(formatted.code, expected_output);
+    assert_eq!(formatted.diagnostics.len(), 1);
+}
+
+#[test]
+fn test_unbalanced_off_region_is_not_reported() {
+    let input = "proc.a\n push.1\nend\n\n# masm-fmt: off\nproc.c\n  nop\n# masm-fmt: on\n\nbegin\n exec.a\nend\n";
+    let expected_output = "proc.a\n    push.1\nend\n\n# masm-fmt: off\nproc.c\n  nop\n# masm-fmt: on\n\nbegin\n    exec.a\nend\n";
+    let formatted = format_code_recovering(input, &FormatConfig::default());
+    assert_eq!(formatted.code, expected_output);
+    assert!(formatted.diagnostics.is_empty());
+}
+
+#[test]
+fn test_blank_line_policy_applies_around_broken_blocks() {
+    let config = FormatConfig::from_toml("[blank_lines]\nbetween_procedures = 2\n").unwrap();
+    let input = "proc.a\n    nop\nend\n\nproc.broken\n  nop\n\nproc.b\n    nop\nend\n";
+    let expected_output = "proc.a\n    nop\nend\n\n\nproc.broken\n  nop\n\nproc.b\n    nop\nend\n";
+    assert_eq!(format_code_recovering(input, &config).code, expected_output);
+}
@@ -0,0 +1,165 @@
+use masm_formatter::{FormatConfig, check_imports, format_code, format_code_with_config};
+
+fn grouped_config() -> FormatConfig {
+    FormatConfig::from_toml("[imports]\ngroups = [\"std::*\", \"miden::*\"]\n")
+        .expect("valid config")
+}
+
+#[test]
+fn test_import_groups_are_separated() {
+    let input = "use.kernel::memory\nuse.miden::note\nuse.std::sys\nuse.miden::account\nuse.std::math::u64\n\nbegin\nend";
+    let expected_output = "use.std::math::u64\nuse.std::sys\n\nuse.miden::account\nuse.miden::note\n\nuse.kernel::memory\n\nbegin\nend\n";
+    assert_eq!(
+        format_code_with_config(input, &grouped_config()),
+        expected_output
+    );
+}
+
+#[test]
+fn test_import_groups_collapse_existing_blank_lines() {
+    let input = "# Imports\nuse.std::sys\n\n\nuse.miden::note\nuse.std::mem\n\nbegin\nend";
+    let expected_output =
+        "# Imports\nuse.std::mem\nuse.std::sys\n\nuse.miden::note\n\nbegin\nend\n";
+    assert_eq!(
+        format_code_with_config(input, &grouped_config()),
+        expected_output
+    );
+}
+
+#[test]
+fn test_import_sort_ignores_alias() {
+    let input = "use.std::math::u64_ext\nuse.std::math::u64->a\n\nbegin\nend";
+    let expected_output = "use.std::math::u64->a\nuse.std::math::u64_ext\n\nbegin\nend\n";
+    assert_eq!(format_code(input), expected_output);
+}
+
+#[test]
+fn test_config_rejects_unknown_fields() {
+    assert!(FormatConfig::from_toml("[imports]\ngroup = []\n").is_err());
+    assert_eq!(
+        FormatConfig::from_toml("").unwrap(),
+        FormatConfig::default()
+    );
+}
+
+#[test]
+fn test_check_imports_reports_unused_and_duplicates() {
+    let input = "use.std::sys\nuse.std::math::u64\nuse.std::math::u64\nuse.miden::note\nuse.kernel::note\n\nbegin\n    exec.u64::add\n    exec.note::get_inputs\nend\n";
+    let messages: Vec<String> = check_imports(input)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "1:1: warning: unused import `std::sys`",
+            "3:1: warning: duplicate import of `std::math::u64`",
+            "5:1: error: the name `note` is imported for both `miden::note` and `kernel::note`",
+        ]
+    );
+}
+
+#[test]
+fn test_remove_unused_imports() {
+    let config = FormatConfig::from_toml("[imports]\nremove_unused = true\n").unwrap();
+    let input = "use.std::sys\nuse.std::math::u64->math\nuse.std::math::u64->math\nuse.miden::account\n\nexport.account::get_id\n\nbegin\n    call.math::add # sys::halt\nend\n";
+    let expected_output = "use.miden::account\nuse.std::math::u64->math\n\nexport.account::get_id\n\nbegin\n    call.math::add # sys::halt\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_hoist_stray_imports() {
+    let config = FormatConfig::from_toml("[imports]\nhoist = true\n").unwrap();
+    let input = "use.std::sys\n\n# Constants\nconst.A=1\nuse.miden::note\n\nproc.foo\n    exec.note::get_inputs\nend\n\nuse.std::math::u64\n\nbegin\n    exec.foo\nend\n";
+    let expected_output = "use.miden::note\nuse.std::math::u64\nuse.std::sys\n\n# Constants\nconst.A=1\n\nproc.foo\n    exec.note::get_inputs\nend\n\nbegin\n    exec.foo\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_stray_import_not_hoisted_across_off_region() {
+    let config = FormatConfig::from_toml("[imports]\nhoist = true\n").unwrap();
+    let input = "use.std::sys\n\n# masm-fmt: off\nconst.A   = 1\n# masm-fmt: on\nuse.std::math::u64\n\nbegin\n    exec.u64::add\n    exec.sys::truncate_stack\nend\n";
+    assert_eq!(format_code_with_config(input, &config), input);
+    let messages: Vec<String> = check_imports(input)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "6:1: warning: import after the import block cannot be hoisted across a `masm-fmt: off` region"
+        ]
+    );
+}
+
+#[test]
+fn test_hoisted_imports_follow_module_doc() {
+    let config = FormatConfig::from_toml("[imports]\nhoist = true\n").unwrap();
+    let input = "#! Module doc.\n\nproc.foo\n    # masm-fmt: off\n    push.1   push.2\n    # masm-fmt: on\n    exec.sys::truncate_stack\nend\n\nuse.std::sys\n";
+    let expected_output = "#! Module doc.\n\nuse.std::sys\n\nproc.foo\n    # masm-fmt: off\n    push.1   push.2\n    # masm-fmt: on\n    exec.sys::truncate_stack\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_modern_import_syntax_is_sorted() {
+    let input = "use std::sys\nuse  miden :: note -> n\nuse.std::math::u64\nuse miden::account # kernel\n\nbegin\nend";
+    let expected_output = "use miden::account # kernel\nuse miden::note->n\nuse.std::math::u64\nuse std::sys\n\nbegin\nend\n";
+    assert_eq!(format_code(input), expected_output);
+}
+
+#[test]
+fn test_modern_import_syntax_is_grouped() {
+    let input = "use miden::note\nuse std::sys\nuse kernel::memory\n\nbegin\n    exec.note::get_inputs\n    exec.sys::truncate_stack\nend\n";
+    let expected_output = "use std::sys\n\nuse miden::note\n\nuse kernel::memory\n\nbegin\n    exec.note::get_inputs\n    exec.sys::truncate_stack\nend\n";
+    assert_eq!(
+        format_code_with_config(input, &grouped_config()),
+        expected_output
+    );
+    let messages: Vec<String> = check_imports(input)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        messages,
+        vec!["3:1: warning: unused import `kernel::memory`"]
+    );
+}
+
+#[test]
+fn test_reexport_spacing_is_normalized() {
+    let input = "export.::miden::util :: account_id::is_equal -> is_id_equal\npub use  std::sys ::truncate_stack\n";
+    let expected_output = "export.::miden::util::account_id::is_equal->is_id_equal\npub use std::sys::truncate_stack\n";
+    assert_eq!(format_code(input), expected_output);
+}
+
+#[test]
+fn test_reexports_are_gathered_and_sorted() {
+    let config = FormatConfig::from_toml("[reexports]\nsort = true\n").expect("valid config");
+    let input = "use.std::sys\n\n#! Zeta.\nexport.memory::zeta\n\nexport.foo\n    nop\nend\n\n#! Equality.\n#! Second line.\nexport.::miden::util::is_equal->alpha\n";
+    let expected_output = "use.std::sys\n\n#! Equality.\n#! Second line.\nexport.::miden::util::is_equal->alpha\n\n#! Zeta.\nexport.memory::zeta\n\nexport.foo\n    nop\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_sorted_reexports_are_stable() {
+    let config = FormatConfig::from_toml("[reexports]\nsort = true\n").expect("valid config");
+    let input = "#! Module docs.\nuse.std::sys\n\n#! Zeta.\nexport.memory::zeta\nexport.::miden::util::is_equal->alpha\nexport.::miden::basic::beta\nproc.foo\n    add\nend\n";
+    let once = format_code_with_config(input, &config);
+    assert_eq!(
+        once,
+        "#! Module docs.\nuse.std::sys\n\nexport.::miden::util::is_equal->alpha\n\nexport.::miden::basic::beta\n\n#! Zeta.\nexport.memory::zeta\nproc.foo\n    add\nend\n"
+    );
+    assert_eq!(format_code_with_config(&once, &config), once);
+}
+
+#[test]
+fn test_module_header_stays_above_sorted_reexports() {
+    let config = FormatConfig::from_toml("[reexports]\nsort = true\n").expect("valid config");
+    let input = "# The MASM code.\n#\nexport.::miden::basic::receive_asset\nexport.::miden::basic::create_note\nexport.::miden::util::is_equal->is_id_equal\nproc.test_proc\n    add\nend";
+    let once = format_code_with_config(input, &config);
+    assert_eq!(
+        once,
+        "# The MASM code.\n#\nexport.::miden::basic::create_note\nexport.::miden::util::is_equal->is_id_equal\nexport.::miden::basic::receive_asset\nproc.test_proc\n    add\nend\n"
+    );
+    assert_eq!(format_code_with_config(&once, &config), once);
+}
@@ -0,0 +1,70 @@
+use masm_formatter::{FormatConfig, format_code, format_code_with_config};
+
+#[test]
+fn test_canonicalize_instruction_aliases() {
+    let config = FormatConfig::from_toml("[instructions]\ncanonicalize = true\n").unwrap();
+    let input = "begin\n    dup.0 dup.1 swap.1\n    movup.2 movdn.2 # dup.0\n    swapw.1 dupw.0 movupw.2\n    emit.event(\"dup.0 swap.1\")\nend\n";
+    let expected_output = "begin\n    dup dup.1 swap\n    movup.2 movdn.2 # dup.0\n    swapw dupw movupw.2\n    emit.event(\"dup.0 swap.1\")\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_canonicalize_is_opt_in() {
+    let input = "begin\n    dup.0 swap.1\nend\n";
+    assert_eq!(format_code(input), input);
+}
+
+#[test]
+fn test_merge_push_instructions() {
+    let config = FormatConfig::from_toml("[instructions]\npush = \"merge\"\n").unwrap();
+    let input = "begin\n    push.1 push.0 add\n    push.1\n    push.0x10.2 # two\n    push.3\n    # => [3, ...]\n\n    push.4\n    push.CONST push.5\nend\n";
+    let expected_output = "begin\n    push.1.0 add\n    push.1.0x10.2 # two\n    push.3\n    # => [3, ...]\n\n    push.4\n    push.CONST push.5\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_merge_push_respects_immediate_limit() {
+    let config = FormatConfig::from_toml("[instructions]\npush = \"merge\"\n").unwrap();
+    let values: Vec<String> = (0..18).map(|i| format!("push.{i}")).collect();
+    let input = format!("begin\n    {}\nend\n", values.join(" "));
+    let expected_output = format!(
+        "begin\n    push.{} push.16.17\nend\n",
+        (0..16).map(|i| i.to_string()).collect::<Vec<_>>().join(".")
+    );
+    assert_eq!(format_code_with_config(&input, &config), expected_output);
+}
+
+#[test]
+fn test_split_push_instructions() {
+    let config = FormatConfig::from_toml("[instructions]\npush = \"split\"\n").unwrap();
+    let input = "begin\n    push.1.0 # flags\n    push.A.B\nend\n";
+    let expected_output = "begin\n    push.1 push.0 # flags\n    push.A.B\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_one_instruction_per_line() {
+    let config = FormatConfig::from_toml("[instructions]\nlayout = \"one-per-line\"\n").unwrap();
+    let input = "proc.foo\n    movup.3 movup.3 # swap pairs\n    dup dup.2 eq\n    if.true\n        drop\n    end\nend\n";
+    let expected_output = "proc.foo\n    movup.3\n    movup.3 # swap pairs\n    dup\n    dup.2\n    eq\n    if.true\n        drop\n    end\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_packed_instruction_layout() {
+    let config =
+        FormatConfig::from_toml("max_width = 24\n\n[instructions]\nlayout = \"packed\"\n").unwrap();
+    let input = "proc.foo\n    dup\n    dup.2\n    eq\n    movdn.3\n    swap\n    # => [a, b]\n\n    drop\n    add # sum\n    mul\n    if.true\n        drop\n        drop\n    end\nend\n";
+    let expected_output = "proc.foo\n    dup dup.2 eq movdn.3\n    swap\n    # => [a, b]\n\n    drop\n    add # sum\n    mul\n    if.true\n        drop drop\n    end\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_layout_leaves_advice_map_values_alone() {
+    let input = "adv_map.A=[\n    1, # one\n    2,\n    3\n]\n\nbegin\n    push.1\nend\n";
+    for layout in ["packed", "one-per-line"] {
+        let config =
+            FormatConfig::from_toml(&format!("[instructions]\nlayout = \"{layout}\"\n")).unwrap();
+        assert_eq!(format_code_with_config(input, &config), input);
+    }
+}
@@ -0,0 +1,29 @@
+use masm_formatter::{FormatConfig, format_code, format_code_with_config};
+
+#[test]
+fn test_normalize_literals() {
+    let config =
+        FormatConfig::from_toml("[literals]\nnormalize = true\nhex_case = \"lower\"\n").unwrap();
+    let input = "const.A=0X1F\nconst.B=00012 # 00012\n\nbegin\n    push.0X1F push.00012.0.000\n    u32lt.0xABCD emit.event(\"0X1F\")\nend\n";
+    let expected_output = "const.A=0x1f\nconst.B=12 # 00012\n\nbegin\n    push.0x1f push.12.0.0\n    u32lt.0xabcd emit.event(\"0X1F\")\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_pad_hex_literals_within_a_felt() {
+    let config =
+        FormatConfig::from_toml("[literals]\nhex_pad = 8\nhex_case = \"upper\"\n").unwrap();
+    let word = format!("0x{}", "ab".repeat(32));
+    let input = format!("begin\n    push.0x1f push.0x123456789 push.{word}\nend\n");
+    let expected_output = format!(
+        "begin\n    push.0x0000001F push.0x0000000123456789 push.0x{}\nend\n",
+        "AB".repeat(32)
+    );
+    assert_eq!(format_code_with_config(&input, &config), expected_output);
+}
+
+#[test]
+fn test_literals_untouched_by_default() {
+    let input = "begin\n    push.0X1F push.00012\nend\n";
+    assert_eq!(format_code(input), input);
+}
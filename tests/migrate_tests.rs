@@ -0,0 +1,101 @@
+use masm_formatter::{
+    FormatConfig, Syntax, check_syntax, format_code_recovering, format_code_with_config,
+    migrate_code,
+};
+
+#[test]
+fn test_migrate_to_modern() {
+    let input = "use.std::sys\n\nconst.X=1\n\nexport.std::math::u64::add\n\n#! Does things.\nexport.foo.2 # main\n    loc_store.0\nend\n\nproc.helper\n    push.X\nend\n";
+    let expected_output = "use std::sys\n\nconst X = 1\n\npub use std::math::u64::add\n\n#! Does things.\n@locals(2)\npub proc foo # main\n    loc_store.0\nend\n\nproc helper\n    push.X\nend\n";
+
+    let migration = migrate_code(input, Syntax::Modern);
+    assert_eq!(migration.code, expected_output);
+    assert!(migration.diagnostics.is_empty());
+
+    let migration = migrate_code(expected_output, Syntax::Legacy);
+    assert_eq!(migration.code, input);
+    assert!(migration.diagnostics.is_empty());
+}
+
+#[test]
+fn test_migrate_reports_what_it_cannot_migrate() {
+    let input = "proc.foo.NUM_LOCALS\n    nop\nend\n\npub proc gt(rhs: word, lhs: word) -> i1\n    nop\nend\n\n# masm-fmt: skip\nexport.bar\n    nop\nend\n";
+
+    let migration = migrate_code(input, Syntax::Modern);
+    assert_eq!(
+        migration
+            .diagnostics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec![
+            "1:1: error: cannot migrate procedure declaration `proc.foo.NUM_LOCALS`",
+            "10:1: warning: line not migrated: it is covered by a formatter directive",
+        ]
+    );
+
+    let migration = migrate_code(input, Syntax::Legacy);
+    assert_eq!(
+        migration.diagnostics[1].to_string(),
+        "5:1: error: cannot migrate typed signature of `gt`"
+    );
+}
+
+#[test]
+fn test_mixed_dialects_are_reported() {
+    let input = "use.std::sys\n\npub proc foo\n    exec.sys::x\nend\n\nexport.bar\n    nop\nend\n\n# masm-fmt: skip\nexport.baz\n    nop\nend\n";
+    let messages = |syntax| {
+        check_syntax(input, syntax)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        messages(None),
+        vec!["7:1: error: `export.bar` uses the legacy syntax, but line 3 uses the modern syntax"]
+    );
+    assert_eq!(
+        messages(Some(Syntax::Modern)),
+        vec![
+            "7:1: error: `export.bar` uses the legacy syntax, but the configured syntax is modern",
+        ]
+    );
+}
+
+#[test]
+fn test_contradicting_constant_syntax_is_rejected() {
+    let config = "syntax = \"modern\"\n\n[constants]\nsyntax = \"legacy\"\n";
+    assert!(FormatConfig::from_toml(config).is_err());
+    assert!(
+        FormatConfig::from_toml("syntax = \"modern\"\n\n[constants]\nsyntax = \"modern\"\n")
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_format_keeps_configured_dialect() {
+    let config = FormatConfig::from_toml("syntax = \"legacy\"\n").unwrap();
+    let input = "use std::sys\n\n@locals(1)\npub proc foo\nexec.sys::x\nend\n";
+    let expected_output = "use.std::sys\n\nexport.foo.1\n    exec.sys::x\nend\n";
+    assert_eq!(format_code_with_config(input, &config), expected_output);
+}
+
+#[test]
+fn test_format_reports_declarations_left_in_other_dialect() {
+    let config = FormatConfig::from_toml("syntax = \"legacy\"\n").unwrap();
+    let input = "proc.broken\n    nop\n\npub proc typed(a: felt) -> felt\n    nop\nend\n\n# masm-fmt: skip\nuse.std::sys\n";
+    let formatted = format_code_recovering(input, &config);
+    assert_eq!(formatted.code, input);
+    assert_eq!(
+        formatted
+            .diagnostics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec![
+            "1:1: error: `proc.broken` is never closed with `end`",
+            "4:1: error: cannot migrate typed signature of `typed`",
+        ]
+    );
+}